@@ -0,0 +1,53 @@
+//! Compare validation throughput with and without the dispatch index.
+//!
+//! Run with `cargo bench --bench validate`
+use jsp::{graph::testdata::{build_graph, build_graph_unindexed}, validate_path, JGraph};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 100_000;
+
+// a mix of valid and invalid paths exercising the show, sequence, shot, and shared levels
+const PATHS: &[&str] = &[
+    "/dd/shows/DEV01",
+    "/dd/shows/DEV01/RD/9999",
+    "/dd/shows/DEV01/RD/9999/user/work.jgerber",
+    "/dd/shows/DEV01/SHARED/MODEL/char/model/bob",
+    "/dd/shows/DEV01/CONFORM/SHARED/MODEL/char",
+    "/dd/shows/DEV01/RD/9999/SHARED/ANIM/prop/anim",
+    "/dd/shows/DEV01/RD/9999/FOO/SHARED/MODEL",
+    "/dd/shows/DEV01/ASSETDEV/BOB_THE_BUILDER/user/work.jgerber",
+];
+
+fn time(graph: &JGraph) -> Duration {
+    let start = Instant::now();
+    let mut valid = 0;
+    for i in 0..ITERATIONS {
+        if validate_path(PATHS[i % PATHS.len()], graph).is_ok() {
+            valid += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    assert!(valid > 0);
+    elapsed
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let per = elapsed.as_nanos() / ITERATIONS as u128;
+    println!("{:<12} {:>10.3}ms total {:>8}ns/path", name, elapsed.as_secs_f64() * 1000.0, per);
+}
+
+fn main() {
+    let unindexed = build_graph_unindexed();
+    let indexed = build_graph();
+
+    // warm up
+    time(&unindexed);
+    time(&indexed);
+
+    let linear = time(&unindexed);
+    let dispatch = time(&indexed);
+    println!("validate_path x {}", ITERATIONS);
+    report("linear", linear);
+    report("dispatch", dispatch);
+    println!("speedup      {:>10.2}x", linear.as_secs_f64() / dispatch.as_secs_f64());
+}
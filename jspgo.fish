@@ -0,0 +1,7 @@
+# jsp go writes shell code to stdout only when it succeeds. Diagnostics go to
+# stderr, and failures exit with 2 (not found), 3 (invalid), or 1 (internal error).
+function jspgo
+    set -l res (jsp go --shell=fish $argv)
+    or return $status
+    eval (string join \n -- $res)
+end
@@ -1,13 +1,12 @@
-cmd="jsp go --shell=bash"
-
+# jsp go writes shell code to stdout only when it succeeds. Diagnostics go to
+# stderr, and failures exit with 2 (not found), 3 (invalid), or 1 (internal error).
 function jspgo { 
-    FOO=$($cmd "$@") > /dev/null; 
-    if [[ $FOO =~ \s*(Error|Info).* ]] ;
-    then
-        echo $FOO 
-    else
-        eval $FOO
-    fi
+    local res
+    res=$(jsp go --shell=bash "$@") || return $?
+    eval "$res"
 }
 export JSP_PATH=~/etc/template.jspt
-export DD_ROOT=dd
\ No newline at end of file
+export DD_ROOT=dd
+eval "$(jsp completion bash)"
+# show the current job location in the prompt. run `jsp cache` after changing the template
+# PS1='$(jsp prompt) '"$PS1"
@@ -0,0 +1,5 @@
+set -gx JSPLOC ~/bin
+set -gx JSP_PATH ~/etc/template.jspt
+set -gx DD_ROOT dd
+source $JSPLOC/jspgo.fish
+jsp completion fish | source
@@ -0,0 +1,199 @@
+//! Addresses locate any branch of the template beneath a levelspec, in the form
+//! `levelspec:entry/rest` (eg `dev01:assetdev/BOBCHAR` or
+//! `dev01.rd.0001:shared/model/char/bob`).
+//!
+//! The entry is either the name of a node tagged with the `entry` metadata key,
+//! which may sit any number of fixed directories beneath the levelspec, or the
+//! name of a fixed directory immediately beneath the levelspec. Fixed
+//! directories are matched case insensitively. The rest of the address is
+//! appended verbatim, and the resulting path is validated against the template.
+//!
+//! ```text
+//! assetdev = ASSETDEV [ entry: assetdev ]
+//! ```
+use crate::{
+    cli,
+    JGraph,
+    JSPError,
+    Levels,
+    NIndex,
+    NodeType,
+    ValidPath,
+};
+use petgraph::Direction::Outgoing;
+use std::collections::VecDeque;
+
+/// A levelspec, an entry point beneath it, and an optional relative path
+/// beneath the entry point
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Address {
+    /// The levelspec (eg `dev01.rd.0001`), which may be relative
+    pub levelspec: String,
+    /// The name of the entry point (eg `assetdev`)
+    pub entry: String,
+    /// The components of the path beneath the entry point
+    pub rest: Vec<String>,
+}
+
+impl Address {
+    /// Parse an address of the form `levelspec:entry/rest`. Returns None if
+    /// `term` is not an address - that is, if it has no `:`, or if the part
+    /// before the `:` contains a `/`.
+    pub fn parse(term: &str) -> Option<Self> {
+        let colon = term.find(':')?;
+        let (levelspec, remainder) = (&term[..colon], &term[colon+1..]);
+        if levelspec.is_empty() || levelspec.contains('/') {
+            return None;
+        }
+        let mut components = remainder.split('/').filter(|c| !c.is_empty()).map(|c| c.to_string());
+        let entry = components.next()?;
+        Some(Self {
+            levelspec: levelspec.to_string(),
+            entry,
+            rest: components.collect(),
+        })
+    }
+
+    /// Resolve the address to a ValidPath
+    ///
+    /// # Parameters
+    ///
+    /// * `graph` - Reference to the JGraph
+    /// * `datetime_dir` - Append a datetime directory to the resolved path
+    ///
+    /// # Returns
+    /// Ok wrapping the ValidPath if the address names a valid location.
+    /// A JSPError::FindFailure if the entry is unknown, or any error resulting
+    /// from resolving the levelspec or validating the path.
+    pub fn resolve<'a>(&self, graph: &'a JGraph, datetime_dir: bool) -> Result<ValidPath<'a>, JSPError> {
+        let terms = cli::gen_terms_from_strings(vec![self.levelspec.clone()], &Levels::of(graph))?;
+        let base = ValidPath::new_from_searchterms(terms, graph, None, false)?;
+        let idx = base.nodepath().nindex().ok_or_else(||
+            JSPError::FindFailure(format!("levelspec '{}' resolves to an empty path", self.levelspec))
+        )?;
+        let (dirs, entry_idx) = find_entry(&self.entry, idx, graph).ok_or_else(||
+            JSPError::FindFailure(format!("no entry '{}' beneath {}", self.entry, base.path().display()))
+        )?;
+
+        let mut pathbuf = base.pathbuf();
+        pathbuf.extend(dirs);
+        // correct the case of any fixed directories, until the first which isn't
+        let mut current = Some(entry_idx);
+        for component in &self.rest {
+            let child = current.and_then(|idx| simple_child(component, idx, graph));
+            match child {
+                Some((name, child)) => {
+                    pathbuf.push(name);
+                    current = Some(child);
+                }
+                None => {
+                    pathbuf.push(component);
+                    current = None;
+                }
+            }
+        }
+        if datetime_dir {
+            pathbuf.push(cli::gen_datetime_dir());
+        }
+        ValidPath::new(pathbuf, graph, false)
+    }
+}
+
+// find the entry named `entry` beneath `idx`, returning the directories leading
+// to it along with its index. Nodes declaring the entry are found breadth first
+// through fixed directories. Failing that, a fixed directory immediately beneath
+// `idx` whose name matches is used.
+fn find_entry(entry: &str, idx: NIndex, graph: &JGraph) -> Option<(Vec<String>, NIndex)> {
+    let mut queue = VecDeque::new();
+    queue.push_back((idx, Vec::new()));
+    let mut visited = vec![idx];
+    while let Some((parent, dirs)) = queue.pop_front() {
+        for child in graph.neighbors_directed(parent, Outgoing) {
+            if visited.contains(&child) {
+                continue;
+            }
+            visited.push(child);
+            let mut child_dirs = dirs.clone();
+            if let NodeType::Simple(name) = graph[child].identity() {
+                child_dirs.push(name.clone());
+            }
+            if graph[child].metadata().entry() == Some(entry) {
+                return Some((child_dirs, child));
+            }
+            if let NodeType::Simple(_) = graph[child].identity() {
+                queue.push_back((child, child_dirs));
+            }
+        }
+    }
+    simple_child(entry, idx, graph).map(|(name, child)| (vec![name], child))
+}
+
+// find the fixed directory beneath idx whose name matches `name`, ignoring case
+fn simple_child(name: &str, idx: NIndex, graph: &JGraph) -> Option<(String, NIndex)> {
+    graph.neighbors_directed(idx, Outgoing).find_map(|child| match graph[child].identity() {
+        NodeType::Simple(simple) if simple.eq_ignore_ascii_case(name) => Some((simple.clone(), child)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::testdata::build_graph, jspnode, EntryType, Node, Regexp};
+    use std::path::Path;
+
+    #[test]
+    fn can_parse_address() {
+        assert_eq!(
+            Address::parse("dev01.rd.0001:shared/model/char/bob"),
+            Some(Address {
+                levelspec: "dev01.rd.0001".to_string(),
+                entry: "shared".to_string(),
+                rest: vec!["model".to_string(), "char".to_string(), "bob".to_string()],
+            })
+        );
+        assert_eq!(Address::parse("dev01:assetdev").map(|a| a.rest), Some(Vec::new()));
+        assert_eq!(Address::parse("dev01.rd"), None);
+        assert_eq!(Address::parse("/dd/shows/DEV01:foo"), None);
+        assert_eq!(Address::parse("dev01:"), None);
+    }
+
+    #[test]
+    fn can_resolve_fixed_directory() {
+        let graph = build_graph();
+        let address = Address::parse("dev01.rd.9999:shared").unwrap();
+        let validpath = address.resolve(&graph, false).unwrap();
+        assert_eq!(validpath.path(), Path::new("/dd/shows/DEV01/RD/9999/SHARED"));
+    }
+
+    #[test]
+    fn unknown_entry_is_err() {
+        let graph = build_graph();
+        let address = Address::parse("dev01.rd.9999:nosuchentry").unwrap();
+        assert!(address.resolve(&graph, false).is_err());
+    }
+
+    #[test]
+    fn can_resolve_assetdev() {
+        let graph = build_graph();
+        let validpath = Address::parse("dev01:assetdev/BOBCHAR").unwrap().resolve(&graph, false).unwrap();
+        assert_eq!(validpath.path(), Path::new("/dd/shows/DEV01/ASSETDEV/BOBCHAR"));
+    }
+
+    #[test]
+    fn can_resolve_declared_entry() {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let shows = graph.add_node(jspnode!("shows"));
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[A-Z0-9]*$", "varname" => "DD_SHOW"));
+        let assets = graph.add_node(jspnode!("ASSETS"));
+        let chars = graph.add_node(jspnode!("CHARACTERS", "entry" => "chars"));
+        let character = graph.add_node(jspnode!("character", r"^[a-z]+$"));
+        graph.extend_with_edges(&[(root, dd), (dd, shows), (shows, show), (show, assets), (assets, chars), (chars, character)]);
+
+        let validpath = Address::parse("dev01:chars/bob").unwrap().resolve(&graph, false).unwrap();
+        assert_eq!(validpath.path(), Path::new("/dd/shows/DEV01/ASSETS/CHARACTERS/bob"));
+        assert!(Address::parse("dev01:chars/BOB").unwrap().resolve(&graph, false).is_err());
+    }
+}
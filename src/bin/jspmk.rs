@@ -5,21 +5,14 @@ use fern::{ colors::{Color, ColoredLevelConfig}, self} ;
 use jsp::{ 
     cli, 
     DiskType, 
-    find_rel, 
-    FindRelStrategy, 
-    get_graph,
     get_graph_from_fn,
-    JGraph, 
+    get_show_etc_dir,
     JSPError,
-    MetadataTerm, 
     //Navalias, 
-    NIndex, 
     //parse_show_from_arg, 
     report, 
     //SearchTerm,
-    ValidPath
 };
-use levelspecter::{LevelSpec, LevelType};
 use log::{ LevelFilter, self };
 use std::{path::PathBuf, convert::AsRef};
 use structopt::StructOpt;
@@ -41,8 +34,8 @@ struct Opt {
     #[structopt( short = "i", long = "input", parse(from_os_str) )]
     graph: Option<PathBuf>,
 
-    /// one or more search tearms of the form key:value , or a 
-    /// fullpath, depending upon other field
+    /// one or more search tearms of the form key:value , a fullpath, or an 
+    /// address of the form levelspec:entry/path (eg dev01:assetdev/BOBCHAR)
     #[structopt(name="TERMS")]
     terms: Vec<String>,
     
@@ -68,6 +61,11 @@ struct Opt {
     #[structopt(short = "f", long = "fullpath")]
     full_path: bool,
 
+    /// match partial or abbreviated levels (eg dev.r.99) against the existing
+    /// locations
+    #[structopt(long = "fuzzy")]
+    fuzzy: bool,
+
     /*
     /// Use new method for finding show template
     #[structopt(short = "z", long = "new")]
@@ -89,7 +87,7 @@ fn doit(args: Opt, level: LevelFilter) -> Result<(), /*failure::Error*/ JSPError
         DiskType::Local
     };
 
-    let Opt{graph, terms, autocreate, sticky, datetime_dir, novolume, full_path, /*newfind,*/ verbose,..} = args;
+    let Opt{graph, terms, autocreate, sticky, datetime_dir, novolume, full_path, fuzzy, /*newfind,*/ verbose,..} = args;
     if terms.len() == 0 {
         eprintln!("Must supply at least one term as input. See help");
         Opt::clap().print_help().unwrap();
@@ -99,30 +97,7 @@ fn doit(args: Opt, level: LevelFilter) -> Result<(), /*failure::Error*/ JSPError
 
     let (graph,  _keymap,  _regexmap) =  {//if newfind {
         get_graph_from_fn(graph, &terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(), |_|{ 
-            // get the graph
-            let (graph, keymap, _regexmap) = get_graph(None)?;
-            
-            let term = match LevelSpec::new(&terms[0]) {
-                Ok(ls) => {
-                    let show = ls.show();
-                    if show == &LevelType::Relative { std::env::var("DD_SHOW")? } else { show.to_str().to_owned() } 
-                },
-                //Err(_) => return Err(JSPError::GeneralError(format!("unable to process first term of user input: '{}'",terms[0]))),
-                // we assume that a path was passed in as opposed to a levelspec. We will deal with
-                // possibility of malformed input farther down.
-                Err(_) => terms[0].to_string(),
-
-            };
-            let search = vec![term];
-            // todo handle abs path
-            let mut validpath = cli::validpath_from_terms(search, &graph, false, full_path)?;
-        
-            let idx = keymap.get("show").unwrap();
-            log::trace!("got index {:?}",idx );
-            validpath.remove_past(idx)?;
-
-            let mut pathbuf = validpath.pathbuf();
-            pathbuf.push("etc");
+            let mut pathbuf = get_show_etc_dir(&terms[0], full_path, fuzzy)?;
             pathbuf.push("template.jspt");
             log::info!("Returning template {:?}", pathbuf);
             Ok( pathbuf)
@@ -138,7 +113,7 @@ fn doit(args: Opt, level: LevelFilter) -> Result<(), /*failure::Error*/ JSPError
     };
     */
   
-    let validpath = cli::validpath_from_terms(terms, &graph, datetime_dir, full_path)?;
+    let validpath = cli::validpath_from_terms(terms, &graph, datetime_dir, full_path, fuzzy)?;
     
     let validpath = cli::mk(validpath, &graph, &disktype, sticky, novolume, verbose)?;             
     if let report::Success::Mk(validpath) = validpath {
@@ -146,7 +121,7 @@ fn doit(args: Opt, level: LevelFilter) -> Result<(), /*failure::Error*/ JSPError
             // find relative
         if let Some(idx) = validpath.nodepath().nindex() {
             if autocreate {
-                process_autocreate(&disktype, idx, &validpath, &graph, novolume, verbose);
+                cli::autocreate(&disktype, idx, &validpath, &graph, novolume, verbose)?;
                 
             }
             // now we process any navaliases
@@ -161,53 +136,6 @@ fn doit(args: Opt, level: LevelFilter) -> Result<(), /*failure::Error*/ JSPError
     Ok(())
 }
 
-#[inline]
-fn process_autocreate(disktype: &DiskType, idx: NIndex, validpath: &ValidPath, graph: &JGraph, novolume:bool, verbose: bool) {
-    match find_rel( idx, MetadataTerm::Autocreate, &graph, FindRelStrategy::Deepest) {
-        Err(e) => {eprintln!("Error: unable to find autocreate nodes: {}", e.to_string());}
-        Ok(nodepaths) => {
-            // now we create them
-            for nodepath in nodepaths {
-                // generate a Pathbuf from the current nodepath
-                let cur_pathbuf = match nodepath.to_pathbuf() {
-                    Ok(v) => v,
-                    Err(e) => {
-                        //eprintln!("Error: unable to convert nodepath to pathbuf. skipping nodepath {}",
-                        //    e.to_string());
-                        report::jsperror("Unable to convert nodepath to pathbuf. skipping nodepath.", e, verbose);
-                        continue
-                    } 
-                };
-                // the full pathbuf 
-                let full_pathbuf = validpath.pathbuf().join(cur_pathbuf);
-                // a copy of the cufrent nodepath
-                let mut cur_nodepath_clone = nodepath.clone();
-                // combine the full_pathbuf and the cur_nodepath_clone
-                let mut full_nodepath = validpath.nodepath().clone();
-                full_nodepath.append_unchecked(&mut cur_nodepath_clone.nodes);
-                // build a new validpath from the full pathbuf and fullnodepath
-                let new_validpath = match ValidPath::new_unchecked(full_pathbuf, full_nodepath, true) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        //eprintln!("Error: Unable to create ValidPath. Err: {}",e.to_string());
-                        report::jsperror("Unable to create ValidPath", e, verbose);
-                        continue
-                    }
-                };
-                // it doesnt matter whether otp.sticky is true. for the sudbirs we set sticky to false
-                let _ = match cli::mk(new_validpath, &graph, disktype, false, novolume,  verbose) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        //eprintln!("Error making new subdirectory: {}", e.to_string());
-                        report::jsperror("Problem making automake subdirectory", e, verbose);
-                        continue
-                    }
-                };
-            }
-        }
-    }
-}
-
 // #[inline]
 // fn process_navalias(idx: NIndex, validpath: &ValidPath, graph: &JGraph, verbose: bool) {
 //     match find_rel( idx, MetadataTerm::Navalias, &graph, FindRelStrategy::First) {
@@ -0,0 +1,267 @@
+//! Named bookmarks for job locations, navigated to via `jspgo @name`. A bookmark
+//! stores a levelspec and `key:value` terms, rather than a path, so that it
+//! continues to resolve when the show's template changes.
+//!
+//! Bookmarks are stored per user, in JSP_BOOKMARKS if set, otherwise
+//! `~/.jsp_bookmarks`. Bookmarks shared by everyone working on a show are
+//! stored in the show's `etc/bookmarks.json`. Both files are json objects
+//! mapping bookmark names to bookmarks.
+use crate::{
+    cli,
+    constants,
+    pathcontext::path_components,
+    validate_path,
+    JGraph,
+    JSPError,
+    Levels,
+    Location,
+    NodeType,
+    SearchTerm,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// A levelspec, plus the terms identifying a location beneath it
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// The absolute levelspec (eg `DEV01.RD.9999`)
+    pub levelspec: String,
+    /// `key:value` terms, as supplied to `jsp go` after the levelspec
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terms: Vec<String>,
+}
+
+impl Bookmark {
+    /// New up a Bookmark from the terms supplied to `jsp go` - a levelspec
+    /// followed by zero or more `key:value` terms. Relative levelspecs are made
+    /// absolute using the environment variables named by the graph's levels.
+    pub fn from_terms(terms: &[String], graph: &JGraph) -> Result<Self, JSPError> {
+        let (levelspec, terms) = terms.split_first().ok_or(JSPError::EmptyArgumentListError)?;
+        if levelspec.contains('/') {
+            return Err(JSPError::BookmarkError(format!("expected a levelspec, not a path: {}", levelspec)));
+        }
+        let levelspec = Levels::of(graph).parse(levelspec)?.join(".");
+        for term in terms {
+            SearchTerm::from_str(term)?;
+        }
+        Ok(Self {
+            levelspec,
+            terms: terms.to_vec(),
+        })
+    }
+
+    /// New up a Bookmark for the nearest valid location to `path`. The location
+    /// is described by its levelspec, and a `key:value` term for each regex node
+    /// beneath the levelspec. As fixed directories are not recorded, locations
+    /// which the bookmark would not navigate back to are rejected.
+    pub fn from_path<I: Into<PathBuf>>(path: I, graph: &JGraph) -> Result<Self, JSPError> {
+        let location = Location::new(path, graph)?;
+        let levelspec = location.levelspec.clone().ok_or_else(||
+            JSPError::BookmarkError(format!("{} is not within a show", location.location().display()))
+        )?;
+        let nodepath = validate_path(location.location(), graph)?;
+        let components = path_components(location.location());
+
+        let levels = Levels::of(graph);
+        let mut terms = Vec::new();
+        for (node, value) in nodepath.iter().zip(components.iter()) {
+            if let NodeType::RegEx{name, pattern, ..} = node.identity() {
+                if levels.contains(name) {
+                    continue;
+                }
+                // regexes with a single capture group are searched for by the
+                // value of the capture, rather than the value of the component
+                let capture = if pattern.capture_names().count() == 2 {
+                    pattern.captures(value).and_then(|caps| caps.get(1)).map(|m| m.as_str().to_string())
+                } else {
+                    None
+                };
+                terms.push(format!("{}:{}", name, capture.unwrap_or_else(|| value.clone())));
+            }
+        }
+        let bookmark = Self { levelspec, terms };
+        let validpath = cli::validpath_from_terms(bookmark.to_terms(), graph, false, false, false)?;
+        if validpath.path() != location.location() {
+            return Err(JSPError::BookmarkError(format!(
+                "{} cannot be bookmarked. {} resolves to {}",
+                location.location().display(), bookmark.to_terms().join(" "), validpath.path().display()
+            )));
+        }
+        Ok(bookmark)
+    }
+
+    /// The terms to supply to `jsp go` - the levelspec followed by the `key:value` terms
+    pub fn to_terms(&self) -> Vec<String> {
+        let mut terms = vec![self.levelspec.clone()];
+        terms.extend(self.terms.iter().cloned());
+        terms
+    }
+
+    /// The show which the bookmark is within
+    pub fn show(&self) -> &str {
+        self.levelspec.split('.').next().unwrap_or("")
+    }
+}
+
+/// A collection of named Bookmarks, backed by a file
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bookmarks {
+    file: PathBuf,
+    bookmarks: BTreeMap<String, Bookmark>,
+}
+
+impl Bookmarks {
+    /// Retrieve the path to the user's bookmarks file. This is JSP_BOOKMARKS if
+    /// set, or ~/.jsp_bookmarks
+    pub fn user_file() -> Option<PathBuf> {
+        if let Ok(file) = env::var(constants::JSP_BOOKMARKS) {
+            return Some(PathBuf::from(file));
+        }
+        env::var("HOME").ok().map(|home| Path::new(&home).join(constants::JSP_BOOKMARKS_FILE))
+    }
+
+    /// Retrieve the path to the shared bookmarks file, given the show's etc directory
+    pub fn shared_file(etc_dir: &Path) -> PathBuf {
+        etc_dir.join(constants::JSP_SHARED_BOOKMARKS)
+    }
+
+    /// Load the user's bookmarks
+    pub fn load_user() -> Result<Self, JSPError> {
+        let file = Self::user_file()
+                    .ok_or_else(|| JSPError::GeneralError("Unable to determine the bookmarks file".to_string()))?;
+        Self::from_file(file)
+    }
+
+    /// Load the bookmarks from `file`. A missing file holds no bookmarks.
+    pub fn from_file<I: Into<PathBuf>>(file: I) -> Result<Self, JSPError> {
+        let file = file.into();
+        let bookmarks = if file.exists() {
+            let reader = BufReader::new(File::open(&file)?);
+            serde_json::from_reader(reader)
+                .map_err(|e| JSPError::BookmarkError(format!("unable to read {}: {}", file.display(), e)))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self{ file, bookmarks })
+    }
+
+    /// The file backing the bookmarks
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Retrieve the bookmark called `name`
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.bookmarks.get(name)
+    }
+
+    /// Iterate over the bookmarks, in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Bookmark)> {
+        self.bookmarks.iter()
+    }
+
+    /// Add a bookmark, replacing any existing bookmark of the same name. The
+    /// bookmarks are not saved until `save` is called.
+    pub fn insert(&mut self, name: &str, bookmark: Bookmark) -> Result<Option<Bookmark>, JSPError> {
+        if !is_bookmark_name(name) {
+            return Err(JSPError::BookmarkError(format!("invalid name '{}'", name)));
+        }
+        Ok(self.bookmarks.insert(name.to_string(), bookmark))
+    }
+
+    /// Write the bookmarks to their file, via a temporary file
+    pub fn save(&self) -> Result<(), JSPError> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.file.with_extension(format!("tmp.{}", std::process::id()));
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            serde_json::to_writer_pretty(&mut writer, &self.bookmarks)
+                .map_err(|e| JSPError::GeneralError(e.to_string()))?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+        fs::rename(&tmp, &self.file)?;
+        Ok(())
+    }
+}
+
+/// Bookmark names are made up of alphanumerics, `_`, `-` and `.`
+pub fn is_bookmark_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+}
+
+/// Parse a reference to a bookmark - `@name`. Returns the name, or None if
+/// `term` is not a reference.
+pub fn parse_bookmark_reference(term: &str) -> Option<&str> {
+    if term.starts_with('@') && is_bookmark_name(&term[1..]) {
+        Some(&term[1..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::testdata::build_graph, testutil::TempDir};
+
+    #[test]
+    fn can_parse_bookmark_reference() {
+        assert_eq!(parse_bookmark_reference("@comp"), Some("comp"));
+        assert_eq!(parse_bookmark_reference("@"), None);
+        assert_eq!(parse_bookmark_reference("@co mp"), None);
+        assert_eq!(parse_bookmark_reference("comp"), None);
+    }
+
+    #[test]
+    fn can_bookmark_terms() {
+        let graph = build_graph();
+        let bookmark = Bookmark::from_terms(&["dev01.rd.9999".to_string(), "work:jgerber".to_string()], &graph).unwrap();
+        assert_eq!(bookmark.levelspec, "DEV01.RD.9999");
+        assert_eq!(bookmark.show(), "DEV01");
+        assert_eq!(bookmark.to_terms(), vec!["DEV01.RD.9999".to_string(), "work:jgerber".to_string()]);
+        assert!(Bookmark::from_terms(&["/dd/shows/DEV01".to_string()], &graph).is_err());
+    }
+
+    #[test]
+    fn can_bookmark_path() {
+        let graph = build_graph();
+        let bookmark = Bookmark::from_path("/dd/shows/DEV01/RD/9999/user/work.jgerber", &graph).unwrap();
+        assert_eq!(bookmark.levelspec, "DEV01.RD.9999");
+        assert_eq!(bookmark.terms, vec!["work:jgerber".to_string()]);
+
+        let validpath = cli::validpath_from_terms(bookmark.to_terms(), &graph, false, false, false).unwrap();
+        assert_eq!(validpath.path(), Path::new("/dd/shows/DEV01/RD/9999/user/work.jgerber"));
+    }
+
+    #[test]
+    fn cannot_bookmark_fixed_dir_beneath_levelspec() {
+        let graph = build_graph();
+        let result = Bookmark::from_path("/dd/shows/DEV01/RD/9999/tools/package", &graph);
+        assert!(matches!(result, Err(JSPError::BookmarkError(_))));
+    }
+
+    #[test]
+    fn can_save_and_reload_bookmarks() {
+        let tmp = TempDir::new("bookmarks_test");
+        let file = tmp.join("bookmarks.json");
+        let mut bookmarks = Bookmarks::from_file(&file).unwrap();
+        assert!(bookmarks.get("comp").is_none());
+        let bookmark = Bookmark{ levelspec: "DEV01.RD".to_string(), terms: Vec::new() };
+        bookmarks.insert("comp", bookmark.clone()).unwrap();
+        assert!(bookmarks.insert("bad name", bookmark.clone()).is_err());
+        bookmarks.save().unwrap();
+
+        let bookmarks = Bookmarks::from_file(&file).unwrap();
+        assert_eq!(bookmarks.get("comp"), Some(&bookmark));
+    }
+}
@@ -0,0 +1,217 @@
+//! Validate a large number of candidate paths, or levelspecs, in a single
+//! invocation. Each input line is validated against the same JGraph, and
+//! the result is written out as a single line of json, making the output
+//! suitable for consumption by render farm submission checks and publish
+//! hooks.
+use crate::{cli, diskutils, validate_path, Address, JGraph, JSPError, NodePath, PathContext};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+/// Details describing why an input failed to validate.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct BulkFailure {
+    /// The error message
+    pub error: String,
+    /// The path entry which failed to match the template, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    /// The depth, in path components, at which the failure occured, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u8>,
+    /// The names of the template nodes which the entry was tested against
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<String>,
+}
+
+/// The result of validating a single input line.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct BulkResult {
+    /// The input, as supplied
+    pub input: String,
+    /// Whether or not the input is valid
+    pub valid: bool,
+    /// The path which the input resolves to, if valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The names of the nodes matched, from root to leaf
+    pub nodes: Vec<String>,
+    /// Map of template variable names to values extracted from the path
+    pub vars: BTreeMap<String, String>,
+    /// Failure details if the input is not valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<BulkFailure>,
+}
+
+impl BulkResult {
+    // new up a successful BulkResult from the input and the resolved pathbuf and nodepath
+    fn success(input: &str, pathbuf: PathBuf, nodepath: &NodePath) -> Self {
+        let context = PathContext::from_nodepath(&pathbuf, nodepath);
+        Self {
+            input: input.to_string(),
+            valid: true,
+            path: Some(pathbuf.display().to_string()),
+            nodes: nodepath.iter().map(|n| n.name().to_string()).collect(),
+            vars: context.vars,
+            failure: None,
+        }
+    }
+
+    // new up a failed BulkResult from the input, the graph and the error
+    fn failure(input: &str, graph: &JGraph, error: JSPError) -> Self {
+        let failure = match error {
+            JSPError::ValidationFailure{ref entry, node, depth}
+            | JSPError::ValidationFailureFor{ref entry, node, depth, ..}
+            | JSPError::ValidationFailureAt{ref entry, node, depth, ..} => {
+                BulkFailure {
+                    error: error.to_string(),
+                    entry: Some(entry.to_string_lossy().to_string()),
+                    depth: Some(depth),
+                    expected: graph.neighbors(node).map(|n| graph[n].display_name()).collect(),
+                }
+            }
+            _ => BulkFailure {
+                error: error.to_string(),
+                entry: None,
+                depth: None,
+                expected: Vec::new(),
+            }
+        };
+        Self {
+            input: input.to_string(),
+            valid: false,
+            path: None,
+            nodes: Vec::new(),
+            vars: BTreeMap::new(),
+            failure: Some(failure),
+        }
+    }
+}
+
+/// Validate a single input, which may either be a path, or a levelspec
+/// optionally followed by whitespace separated `key:value` terms.
+///
+/// # Parameters
+///
+/// * `input` - The path or levelspec to validate
+/// * `graph` - Reference to the JGraph
+///
+/// # Returns
+/// A BulkResult describing the outcome.
+pub fn validate_input(input: &str, graph: &JGraph) -> BulkResult {
+    let input = input.trim();
+    if input.contains('/') && Address::parse(input).is_none() {
+        let pathbuf = match diskutils::convert_relative_pathbuf_to_absolute(PathBuf::from(input)) {
+            Ok(p) => p,
+            Err(e) => return BulkResult::failure(input, graph, e),
+        };
+        match validate_path(&pathbuf, graph) {
+            Ok(nodepath) => BulkResult::success(input, pathbuf, &nodepath),
+            Err(e) => BulkResult::failure(input, graph, e),
+        }
+    } else {
+        let terms = input.split_whitespace().map(|x| x.to_string()).collect::<Vec<String>>();
+        match cli::validpath_from_terms(terms, graph, false, false, false) {
+            Ok(validpath) => BulkResult::success(input, validpath.pathbuf(), validpath.nodepath()),
+            Err(e) => BulkResult::failure(input, graph, e),
+        }
+    }
+}
+
+/// Read inputs from `reader`, one per line, validating each and writing a
+/// line of json per input to `writer`. Empty lines and lines starting with
+/// `#` are skipped. The writer is flushed after each line so that the
+/// results may be consumed as they are produced.
+///
+/// # Parameters
+///
+/// * `reader` - Source of inputs, implementing BufRead (eg stdin().lock())
+/// * `writer` - Destination for the json results
+/// * `graph`  - Reference to the JGraph, which is only loaded once by the caller
+///
+/// # Returns
+/// A tuple of (valid count, invalid count) if successful. Otherwise, a JSPError
+pub fn validate_stream<R, W>(reader: R, mut writer: W, graph: &JGraph) -> Result<(usize, usize), JSPError>
+where
+    R: BufRead,
+    W: Write
+{
+    let mut valid = 0;
+    let mut invalid = 0;
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let result = validate_input(trimmed, graph);
+        if result.valid { valid += 1; } else { invalid += 1; }
+        serde_json::to_writer(&mut writer, &result).map_err(|e| JSPError::GeneralError(e.to_string()))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    log::info!("validate_stream(...) valid: {} invalid: {}", valid, invalid);
+    Ok((valid, invalid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph;
+    use std::io::Cursor;
+
+    #[test]
+    fn can_validate_path() {
+        let graph = build_graph();
+        let result = validate_input("/dd/shows/DEV01/RD/9999", &graph);
+        assert!(result.valid);
+        assert_eq!(result.nodes, vec!["root", "dd", "shows", "show", "sequence", "shot"]);
+        assert_eq!(result.vars.get("DD_SHOW"), Some(&"DEV01".to_string()));
+        assert_eq!(result.vars.get("DD_SEQUENCE"), Some(&"RD".to_string()));
+        assert_eq!(result.vars.get("DD_SHOT"), Some(&"9999".to_string()));
+    }
+
+    #[test]
+    fn can_validate_levelspec() {
+        let graph = build_graph();
+        let result = validate_input("DEV01.RD.9999 work:jgerber", &graph);
+        assert!(result.valid);
+        assert_eq!(result.path, Some("/dd/shows/DEV01/RD/9999/user/work.jgerber".to_string()));
+        assert_eq!(result.vars.get("DD_WORK"), Some(&"work.jgerber".to_string()));
+    }
+
+    #[test]
+    fn can_validate_address() {
+        let graph = build_graph();
+        let result = validate_input("dev01:assetdev/BOBCHAR", &graph);
+        assert!(result.valid);
+        assert_eq!(result.path, Some("/dd/shows/DEV01/ASSETDEV/BOBCHAR".to_string()));
+    }
+
+    #[test]
+    fn invalid_path_reports_failure() {
+        let graph = build_graph();
+        let result = validate_input("/dd/shows/DEV01/RD/9999/FOO", &graph);
+        assert!(!result.valid);
+        let failure = result.failure.unwrap();
+        assert_eq!(failure.entry, Some("FOO".to_string()));
+        assert!(!failure.expected.is_empty());
+    }
+
+    #[test]
+    fn can_validate_stream() {
+        let graph = build_graph();
+        let input = Cursor::new("/dd/shows/DEV01\n\n# comment\n/dd/shows/DEV01/RD/9999/FOO\n");
+        let mut output = Vec::new();
+        let (valid, invalid) = validate_stream(input, &mut output, &graph).unwrap();
+        assert_eq!((valid, invalid), (1, 1));
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"input":"/dd/shows/DEV01","valid":true"#));
+        assert!(lines[1].starts_with(r#"{"input":"/dd/shows/DEV01/RD/9999/FOO","valid":false"#));
+    }
+}
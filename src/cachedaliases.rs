@@ -14,8 +14,7 @@ impl std::default::Default for CachedAliases {
     fn default() -> CachedAliases {
         log::info!("CachedAliases::default()");
         let var = env::var(constants::JSP_ALIAS_NAMES).unwrap_or_else(|_| String::from(""));
-        let varnames = var.split(':').filter(|x| x.trim() != "").map(|x| x.to_owned()).collect::<Vec<String>>();
-        Self(varnames)
+        Self::from_names(&var)
     }
 }
 
@@ -32,6 +31,12 @@ impl CachedAliases {
         log::info!("CachedAliases::new()");
         CachedAliases::default()
     }
+
+    /// New up a CachedAliases from a colon separated list of names, as held by
+    /// `constants::JSP_ALIAS_NAMES`, rather than reading it from the environment
+    pub fn from_names(names: &str) -> Self {
+        Self(names.split(':').filter(|x| x.trim() != "").map(|x| x.to_owned()).collect::<Vec<String>>())
+    }
     
     /// Return an iterator over CachedEvnVars
     pub fn iter(&self) -> IterCachedAliases {
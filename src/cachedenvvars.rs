@@ -13,8 +13,7 @@ impl std::default::Default for CachedEnvVars {
     fn default() -> CachedEnvVars {
         log::info!("CachedEnvVars::default()");
         let var = env::var(constants::JSP_TRACKING_VAR).unwrap_or_else(|_| String::from(""));
-        let varnames = var.split(':').filter(|x| x.trim() != "").map(|x| x.to_owned()).collect::<Vec<String>>();
-        Self(varnames)
+        Self::from_names(&var)
     }
 }
 
@@ -31,6 +30,12 @@ impl CachedEnvVars {
         log::info!("CachedEnvVars::new()");
         CachedEnvVars::default()
     }
+
+    /// New up a CachedEnvVars from a colon separated list of names, as held by
+    /// `constants::JSP_TRACKING_VAR`, rather than reading it from the environment
+    pub fn from_names(names: &str) -> Self {
+        Self(names.split(':').filter(|x| x.trim() != "").map(|x| x.to_owned()).collect::<Vec<String>>())
+    }
     
     /// Return an iterator over CachedEvnVars
     pub fn iter(&self) -> IterCachedEnvVars {
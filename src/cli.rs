@@ -1,42 +1,52 @@
 //! Command line oriented utility functions for making and navigating through the 
 //! job system
 use crate::{
+    Address,
     CachedAliases,
     CachedEnvVars,
     constants,
     DiskType,
+    EnvFile,
+    GoOutcome,
+    GoPlan,
+    History,
+    HistoryEntry,
     find_rel,
     FindRelStrategy,
+    fuzzy,
     get_disk_service,
     JGraph,
     JSPError,
+    Levels,
     MetadataTerm,
-    Navalias,
+    navalias::{self, Navalias},
     NIndex,
     Node,
-    NodePath,
+    pathcontext,
+    PathContext,
     report,
+    run,
     SearchTerm,
     SupportedShell,
-    ShellEnvManager,
     ValidPath,
     NodeType,
 };
 use chrono::prelude::*;
-use levelspecter::{LevelSpec, LevelName};
 use std::{
-    collections::VecDeque,
+    collections::BTreeMap,
     env,
-    path::{Component, PathBuf},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
     str::FromStr
 };
 use std::collections::HashMap;
 
 
-type NavaliasMap = HashMap<String, PathBuf>;
+// map of navalias names to the navalias which won, and the path it resolved to
+type NavaliasMap = HashMap<String, (Navalias, PathBuf)>;
 
 /// Generate a ValidPath from input. This input may either be an absolute or 
-/// relative path, a levelspec and terms,
+/// relative path, a levelspec and terms, an address (eg `dev01:assetdev/BOBCHAR`),
 /// or a straight vector of terms. In any case, `validpath_from_terms` will 
 /// attempt to do the right thing.
 /// 
@@ -50,17 +60,31 @@ type NavaliasMap = HashMap<String, PathBuf>;
 ///                      will already attempt to ascertain the nature of the 
 ///                      input. However, there are certain ambiguous scenarios
 ///                      where this is necessary.
+/// * `fuzzy` - Match partial or abbreviated levels of the levelspec (eg `dev.r.99`)
+///             against the existing locations. See `fuzzy_terms`.
 /// 
 /// # Returns
 /// An Ok wrapped ValidPath instance when successful 
 /// An Error wrapped JSPError when unsuccessful
 pub fn validpath_from_terms<'a>(
-    mut terms: Vec<String>, 
+    terms: Vec<String>, 
     graph: &'a JGraph, 
     datetime_dir: bool, 
-    force_fullpath: bool
+    force_fullpath: bool,
+    fuzzy: bool
 ) -> Result<ValidPath<'a>, JSPError> {
-    if force_fullpath || ( !terms.is_empty() && terms[0].contains('/') ) {
+    let mut terms = if fuzzy && !force_fullpath { fuzzy_terms(terms, graph)? } else { terms };
+    // addresses may contain a path separator after the levelspec, so they are 
+    // identified before paths
+    let address = if force_fullpath { None } else { terms.first().and_then(|term| Address::parse(term)) };
+    if let Some(address) = address {
+        if terms.len() > 1 {
+            return Err(JSPError::SearchTermError(
+                format!("unexpected terms following address '{}': {}", terms[0], terms[1..].join(" "))
+            ));
+        }
+        address.resolve(graph, datetime_dir)
+    } else if force_fullpath || ( !terms.is_empty() && terms[0].contains('/') ) {
         let mut pathbuf = PathBuf::from(terms.pop().expect("unable to unwrap"));
         if datetime_dir {
             // construct datetime dir
@@ -69,7 +93,7 @@ pub fn validpath_from_terms<'a>(
         // made this true since we are in the fullpath branch
         ValidPath::new(pathbuf, graph, true)
     } else {
-        let terms = gen_terms_from_strings(terms)?;
+        let terms = gen_terms_from_strings(terms, &Levels::of(graph))?;
         if datetime_dir {
             let dt = gen_datetime_dir();
             ValidPath::new_from_searchterms(terms, graph, Some(dt.as_str()), force_fullpath)
@@ -84,7 +108,7 @@ mod validpath_from_terms_test {
     use super::*;
     use crate::graph;
     use std::path::Path;
-    use crate::{ Node, jspnode, NodeType, EntryType };
+    use crate::{ Node, jspnode, NodeType, EntryType, Regexp, testutil::{graph_to, TempDir} };
 
     fn setup_curdir_graph(dirs: Vec<&str> ) -> JGraph {
         let cwd = std::env::current_dir().unwrap();
@@ -118,21 +142,21 @@ mod validpath_from_terms_test {
     #[test]
     fn fullpath() {
         let graph = graph::testdata::build_graph();
-        let vp = validpath_from_terms(vec!["/dd/shows/FOOBAR".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["/dd/shows/FOOBAR".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR"));
     }
 
     #[test]
     fn levelspec_show() {
         let graph = graph::testdata::build_graph();
-        let vp = validpath_from_terms(vec!["FOOBAR".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["FOOBAR".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR"));
     }
 
     #[test]
     fn levelspec_seq() {
         let graph = graph::testdata::build_graph();
-        let vp = validpath_from_terms(vec!["FOOBAR.RD".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["FOOBAR.RD".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/RD"));
 
     }
@@ -140,7 +164,7 @@ mod validpath_from_terms_test {
     #[test]
     fn levelspec_shot() {
         let graph = graph::testdata::build_graph();
-        let vp = validpath_from_terms(vec!["FOOBAR.RD.9999".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["FOOBAR.RD.9999".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/RD/9999"));
     }
 
@@ -153,7 +177,7 @@ mod validpath_from_terms_test {
         let mut expected = std::env::current_dir().unwrap();
         expected.push("AA");
         expected.push("9999");
-        let vp = validpath_from_terms(vec!["./AA/9999".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["./AA/9999".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), expected.as_path());
     }
     
@@ -168,7 +192,7 @@ mod validpath_from_terms_test {
         expected.pop();
         expected.push("AA");
         expected.push("9999");
-        let vp = validpath_from_terms(vec!["../AA/9999".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["../AA/9999".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), expected.as_path());
     }
     
@@ -178,7 +202,7 @@ mod validpath_from_terms_test {
         std::env::set_var("DD_SEQUENCE", "RD");
         std::env::set_var("DD_SHOT", "9999");
         std::env::set_var("DD_SHOW", "FOOBAR");
-        let vp = validpath_from_terms(vec!["BLA..".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["BLA..".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/BLA/RD/9999"));
     }
 
@@ -188,7 +212,7 @@ mod validpath_from_terms_test {
         std::env::set_var("DD_SEQUENCE", "RD");
         std::env::set_var("DD_SHOT", "9999");
         std::env::set_var("DD_SHOW", "FOOBAR");
-        let vp = validpath_from_terms(vec![".AA.".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec![".AA.".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/AA/9999"));
     }
 
@@ -198,7 +222,7 @@ mod validpath_from_terms_test {
         std::env::set_var("DD_SEQUENCE", "RD");
         std::env::set_var("DD_SHOT", "9999");
         std::env::set_var("DD_SHOW", "FOOBAR");
-        let vp = validpath_from_terms(vec!["..1000".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec!["..1000".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/RD/1000"));
     }
 
@@ -208,13 +232,49 @@ mod validpath_from_terms_test {
         std::env::set_var("DD_SEQUENCE", "RD");
         std::env::set_var("DD_SHOT", "9999");
         std::env::set_var("DD_SHOW", "FOOBAR");
-        let vp = validpath_from_terms(vec![".AA.".to_string()], &graph, false, false).unwrap();
+        let vp = validpath_from_terms(vec![".AA.".to_string()], &graph, false, false, false).unwrap();
         assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/AA/9999"));
     }
+
+    #[test]
+    fn address() {
+        let graph = graph::testdata::build_graph();
+        let vp = validpath_from_terms(vec!["foobar.rd.9999:shared".to_string()], &graph, false, false, false).unwrap();
+        assert_eq!(vp.path(), Path::new("/dd/shows/FOOBAR/RD/9999/SHARED"));
+        let terms = vec!["foobar:assetdev".to_string(), "work:jgerber".to_string()];
+        assert!(validpath_from_terms(terms, &graph, false, false, false).is_err());
+    }
+
+    #[test]
+    fn address_with_path() {
+        let graph = graph::testdata::build_graph();
+        let vp = validpath_from_terms(vec!["dev01:assetdev/BOBCHAR".to_string()], &graph, false, false, false).unwrap();
+        assert_eq!(vp.path(), Path::new("/dd/shows/DEV01/ASSETDEV/BOBCHAR"));
+        let vp = validpath_from_terms(vec!["dev01.rd.9999:shared/MODEL".to_string()], &graph, false, false, false).unwrap();
+        assert_eq!(vp.path(), Path::new("/dd/shows/DEV01/RD/9999/SHARED/MODEL"));
+    }
+
+    #[test]
+    fn fuzzy_levelspec() {
+        let root = TempDir::new("fuzzy_test");
+        root.mkdirs(&["DEV01/RD/9999", "DEV02/RD/0001"]);
+        let (mut graph, parent) = graph_to(root.path());
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[0-9]*$"));
+        let sequence = graph.add_node(jspnode!("sequence", r"^[A-Z]+$"));
+        let shot = graph.add_node(jspnode!("shot", r"^[0-9]+$"));
+        graph.extend_with_edges(&[(parent, show), (show, sequence), (sequence, shot)]);
+        crate::build_dispatch_index(&mut graph);
+
+        let vp = validpath_from_terms(vec!["d1.r.99".to_string()], &graph, false, false, true);
+        let ambiguous = validpath_from_terms(vec!["d.r".to_string()], &graph, false, false, true);
+
+        assert_eq!(vp.unwrap().path(), root.join("DEV01/RD/9999").as_path());
+        assert!(matches!(ambiguous, Err(JSPError::AmbiguousLevelspec{..})));
+    }
 }
 
 // Generate a datetime directory
-fn gen_datetime_dir() -> String {
+pub(crate) fn gen_datetime_dir() -> String {
     let dt: DateTime<Local> = Local::now();
     format!("{}_{}_{}",dt.year(), dt.month(), dt.day())
 }
@@ -279,6 +339,10 @@ pub fn mk<'a>(
 ///                 usual circumstances,  the command will automatically 
 ///                 determine this based on the presence of a  path separator 
 ///                 in the input.
+/// * `fuzzy`     - match partial or abbreviated levels against the existing
+///                 locations. See `fuzzy_terms`.
+/// * `missing`   - what to do if the location is valid, but does not exist. 
+///                 See `MissingTarget`.
 /// * `verbose`   - Output is more extensive, colored, etc.
 /// 
 /// # Returns
@@ -289,172 +353,418 @@ pub fn go<'a> (
     myshell: Option<String>, 
     graph: &'a JGraph,
     full_path: bool, 
+    fuzzy: bool,
+    missing: &MissingTarget,
     verbose: bool
 ) -> Result<ValidPath<'a>, JSPError> {
+    let myshell = myshell.unwrap_or_else(|| "bash".to_string());
+    let myshelldyn = SupportedShell::from_str(myshell.as_str())?.get();
+
+    let (validpath, plan) = go_plan(terms, graph, full_path, fuzzy, verbose)?;
+    let (validpath, plan) = resolve_missing_target(validpath, plan, graph, missing, verbose)?;
+    print!("{}", plan.render(&*myshelldyn));
+
+    // failing to record the visit should not prevent navigation
+    let levelspec = PathContext::new(&plan.target, graph).ok().and_then(|ctx| ctx.levelspec());
+    if let Err(e) = History::load().and_then(|mut history| history.record(HistoryEntry::new(&plan.target, levelspec))) {
+        log::warn!("cli::go(...) unable to record history: {}", e);
+    }
+    Ok(validpath)
+}
+
+/// The policy which `go` applies when the requested location is valid, but 
+/// does not exist on disk.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub enum MissingTarget {
+    /// Navigate to the deepest existing ancestor of the location, with a warning
+    #[default]
+    Ancestor,
+    /// Create the location by running `jspmk`, including any subdirectories tagged 
+    /// with `autocreate`, and navigate to it. See `mk_with_jspmk`
+    Create{ jspmk: PathBuf, ignore_volume: bool },
+    /// Fail with a JSPError::NonExtantPathError
+    Fail,
+}
+
+/// Apply the MissingTarget policy to the results of `go_plan`. If the plan's target 
+/// exists, the validpath and plan are returned untouched. Otherwise, the returned 
+/// plan's `outcome` records whether the target is the nearest existing ancestor of
+/// the requested location, or was created.
+/// 
+/// # Parameters
+/// 
+/// * `validpath` - The ValidPath returned by `go_plan`
+/// * `plan`      - The GoPlan returned by `go_plan`
+/// * `graph`     - an reference to the JGraph describing the jobsystem template 
+/// * `missing`   - The policy to apply
+/// * `verbose`   - Output is more extensive, colored, etc.
+/// 
+/// # Returns
+/// A Result wrapping a tuple of the ValidPath and GoPlan navigated to, or a JSPError 
+/// if the location does not exist and the policy is `Fail`, or it could not be created.
+pub fn resolve_missing_target<'a>(
+    validpath: ValidPath<'a>, 
+    plan: GoPlan, 
+    graph: &'a JGraph,
+    missing: &MissingTarget, 
+    verbose: bool
+) -> Result<(ValidPath<'a>, GoPlan), JSPError> {
+    if plan.target.is_dir() {
+        return Ok((validpath, plan));
+    }
+    match missing {
+        MissingTarget::Fail => Err(JSPError::NonExtantPathError(plan.target)),
+        MissingTarget::Ancestor => {
+            let ancestor = plan.target.ancestors()
+                            .skip(1)
+                            .find(|p| p.is_dir())
+                            .ok_or_else(|| JSPError::NonExtantPathError(plan.target.clone()))?
+                            .to_path_buf();
+            report::shellwarning(
+                format!("{} does not exist. Navigating to {}", plan.target.display(), ancestor.display()), 
+                verbose
+            );
+            let (validpath, mut ancestor_plan) = go_plan(
+                vec![ancestor.to_string_lossy().to_string()], graph, true, false, verbose
+            )?;
+            ancestor_plan.outcome = GoOutcome::Ancestor{ requested: plan.target };
+            Ok((validpath, ancestor_plan))
+        }
+        MissingTarget::Create{jspmk, ignore_volume} => {
+            mk_with_jspmk(jspmk, &plan.target, *ignore_volume)?;
+            let mut plan = plan;
+            plan.outcome = GoOutcome::Created;
+            Ok((validpath, plan))
+        }
+    }
+}
+
+/// Retrieve the path to the `jspmk` installed alongside the running executable.
+/// As jsp may be installed setuid, jspmk is never looked up on the PATH, which 
+/// the user controls.
+pub fn installed_jspmk() -> Result<PathBuf, JSPError> {
+    Ok(env::current_exe()?.with_file_name("jspmk"))
+}
+
+/// Create `target`, along with any subdirectories tagged with `autocreate`, by 
+/// running `jspmk`, which is responsible for creating locations with the correct
+/// ownership. The output of jspmk is discarded, as the output of `go` is evaluated
+/// by the shell.
+/// 
+/// # Parameters
+/// 
+/// * `jspmk`         - The path to jspmk. See `installed_jspmk`
+/// * `target`        - The location to create
+/// * `ignore_volume` - If true, treat all Volume nodes as Directory nodes when
+///                     creating them
+/// 
+/// # Returns
+/// Ok if jspmk succeeds in creating the location. Otherwise, a JSPError.
+pub fn mk_with_jspmk(jspmk: &Path, target: &Path, ignore_volume: bool) -> Result<(), JSPError> {
+    let mut cmd = Command::new(jspmk);
+    cmd.arg("--fullpath").arg("--auto");
+    if ignore_volume {
+        cmd.arg("--novolume");
+    }
+    let status = cmd.arg(target).stdout(Stdio::null()).status()?;
+    if !status.success() || !target.is_dir() {
+        return Err(JSPError::MkdirFailure(target.display().to_string()));
+    }
+    Ok(())
+}
+
+/// Create the subdirectories beneath `validpath` which are tagged with the 
+/// `autocreate` metadata key. Failures are reported and skipped, and then 
+/// returned as a single error.
+/// 
+/// # Parameters
+/// 
+/// * `disktype`      - The type of disk to create the directories on
+/// * `idx`           - The NIndex of the last node of `validpath`
+/// * `validpath`     - The ValidPath beneath which to create the directories
+/// * `graph`         - Reference to the JGraph which describes the jobsytem template
+/// * `ignore_volume` - If true, treat all Volume nodes as Directory nodes when
+///                     creating them
+/// * `verbose`       - Output is more extensive, colored, etc.
+/// 
+/// # Returns
+/// Ok if every subdirectory is created. Otherwise, a JSPError.
+pub fn autocreate(disktype: &DiskType, idx: NIndex, validpath: &ValidPath, graph: &JGraph, ignore_volume: bool, verbose: bool) 
+-> Result<(), JSPError> {
+    let mut failures = Vec::new();
+    match find_rel( idx, MetadataTerm::Autocreate, &graph, FindRelStrategy::Deepest) {
+        Err(e) => {
+            eprintln!("Error: unable to find autocreate nodes: {}", e.to_string());
+            return Err(e);
+        }
+        Ok(nodepaths) => {
+            // now we create them
+            for nodepath in nodepaths {
+                // generate a Pathbuf from the current nodepath
+                let cur_pathbuf = match nodepath.to_pathbuf() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        report::jsperror("Unable to convert nodepath to pathbuf. skipping nodepath.", e, verbose);
+                        failures.push(format!("autocreate subdirectory of {}", validpath.path().display()));
+                        continue
+                    } 
+                };
+                // the full pathbuf 
+                let full_pathbuf = validpath.pathbuf().join(cur_pathbuf);
+                let full_path = full_pathbuf.display().to_string();
+                // a copy of the cufrent nodepath
+                let mut cur_nodepath_clone = nodepath.clone();
+                // combine the full_pathbuf and the cur_nodepath_clone
+                let mut full_nodepath = validpath.nodepath().clone();
+                full_nodepath.append_unchecked(&mut cur_nodepath_clone.nodes);
+                // build a new validpath from the full pathbuf and fullnodepath
+                let new_validpath = match ValidPath::new_unchecked(full_pathbuf, full_nodepath, true) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        report::jsperror("Unable to create ValidPath", e, verbose);
+                        failures.push(full_path);
+                        continue
+                    }
+                };
+                // it doesnt matter whether otp.sticky is true. for the sudbirs we set sticky to false
+                if let Err(e) = mk(new_validpath, &graph, disktype, false, ignore_volume,  verbose) {
+                    report::jsperror("Problem making automake subdirectory", e, verbose);
+                    failures.push(full_path);
+                }
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(JSPError::MkdirFailure(failures.join(", ")))
+    }
+}
+
+/// Generate the shell commands which `go` prints, without printing them. The 
+/// commands are rendered from the GoPlan returned by `go_plan`. 
+/// 
+/// # Parameters
+/// 
+/// See `go`
+/// 
+/// # Returns
+/// A Result wrapping a tuple of the ValidPath and the shell commands if successful, 
+/// or a JSPError if unable to navigate to the supplied directory.
+pub fn go_script<'a> (
+    terms: Vec<String>, 
+    myshell: Option<String>, 
+    graph: &'a JGraph,
+    full_path: bool, 
+    fuzzy: bool,
+    verbose: bool
+) -> Result<(ValidPath<'a>, String), JSPError> {
 
     let myshell = myshell.unwrap_or_else(|| "bash".to_string());
 
     let myshelldyn = SupportedShell::from_str(myshell.as_str())?.get();
 
-    match validpath_from_terms(terms, &graph, false, full_path) {
-        Ok(mut validpath) => {
+    let (validpath, plan) = go_plan(terms, graph, full_path, fuzzy, verbose)?;
+    Ok((validpath, plan.render(&*myshelldyn)))
+}
+
+/// Determine the changes to the environment required to navigate to the location 
+/// described by `terms`, without rendering them for a particular shell.
+/// 
+/// # Parameters
+/// 
+/// * `terms`     - vector of terms representing the navigation request. See `go`
+/// * `graph`     - an reference to the JGraph describing the jobsystem template 
+/// * `full_path` - explicitly declare that the input is a full path. 
+/// * `fuzzy`     - match partial or abbreviated levels against the existing locations.
+/// * `verbose`   - Output is more extensive, colored, etc.
+/// 
+/// # Returns
+/// A Result wrapping a tuple of the ValidPath and the GoPlan if successful, 
+/// or a JSPError if unable to navigate to the supplied directory.
+pub fn go_plan<'a> (
+    terms: Vec<String>, 
+    graph: &'a JGraph,
+    full_path: bool, 
+    fuzzy: bool,
+    verbose: bool
+) -> Result<(ValidPath<'a>, GoPlan), JSPError> {
+
+    match validpath_from_terms(terms, &graph, false, full_path, fuzzy) {
+        Ok(validpath) => {
             if let Some(idx) = validpath.nodepath().nindex() {
                 // now we process any navaliases
-                let mut navalias_map = process_navalias(idx, &validpath, &graph, verbose);
+                let mut navalias_map = process_navalias(idx, &validpath, &graph, Some(verbose));
                 
-                let _ = process_go_success(&mut validpath, &mut navalias_map, myshelldyn, verbose)?;
-                Ok(validpath)
+                let plan = process_go_success(&validpath, &mut navalias_map, verbose)?;
+                Ok((validpath, plan))
             } else {
-                panic!("cli::go(...). Unable to get index NIndex from nodepath");
+                panic!("cli::go_plan(...). Unable to get index NIndex from nodepath");
             }
         },
         
         Err(e) => {
-            report::shellerror("cli::go(...). Problem converting terms to path", Some(e.clone()), verbose);
+            report::shellerror("cli::go_plan(...). Problem converting terms to path", Some(e.clone()), verbose);
             Err(e)
         },
     }
 }
 
+/// Resolve the navaliases which `go` sets on navigating to `validpath`, as a map 
+/// of alias names to paths. Unlike `go_plan`, nothing is reported, and neither the
+/// variables and aliases recorded in the environment, nor env files, are read.
+/// 
+/// # Parameters
+/// 
+/// * `validpath` - The location navigated to
+/// * `graph`     - Reference to the JGraph describing the jobsystem template
+pub fn navaliases(validpath: &ValidPath, graph: &JGraph) -> BTreeMap<String, PathBuf> {
+    let mut navalias_map = match validpath.nodepath().nindex() {
+        Some(idx) => process_navalias(idx, validpath, graph, None),
+        None => return BTreeMap::new(),
+    };
+    let values = template_values(&PathContext::from_validpath(validpath));
+    process_leaf_navalias(validpath, &values, &mut navalias_map, None);
+    navalias_map.into_iter().map(|(name, (_, path))| (name, path)).collect()
+}
+
+// Resolve the navaliases declared beneath the last node of `validpath`. Problems
+// are reported to the shell when `report` is Some(verbose), and otherwise logged.
 #[inline]
-fn process_navalias(idx: NIndex, validpath: &ValidPath, graph: &JGraph, verbose: bool) -> NavaliasMap {
+fn process_navalias(idx: NIndex, validpath: &ValidPath, graph: &JGraph, report: Option<bool>) -> NavaliasMap {
     let mut navaliasmap = NavaliasMap::new();
+    let values = template_values(&PathContext::from_validpath(validpath));
 
     match find_rel( idx, MetadataTerm::Navalias, &graph, FindRelStrategy::First) {
-        Err(e) => { report::shellerror(
-            format!("process_navalias(...). Unable to find navalias nodes: {}", e.to_string()).as_str(),
-            None, 
-            verbose); 
+        Err(e) => match report {
+            Some(verbose) => report::shellerror(
+                format!("process_navalias(...). Unable to find navalias nodes: {}", e.to_string()).as_str(),
+                None, 
+                verbose
+            ),
+            None => log::debug!("process_navalias(...). Unable to find navalias nodes: {}", e),
         }
         Ok(nodepaths) => {
-            // now we create them
             for mut nodepath in nodepaths {
                 
                 let last = nodepath.pop().expect("process_navalias(...). Could not unwrap the nindex pop'ed off the nodepath");
                 let lastnode = &graph[last];
-
-                match process_navalias_entry(&validpath, &nodepath, &lastnode, &mut navaliasmap){
-                        Err(e) => {
-                            report::shellerror("process_navalias(...). Unable to convert nodepath to pathbuf. skipping nodepath.", Some(e), verbose);
-                        }
-                        _ => {}
-                    };
-                /*
-                match nodepath.to_pathbuf() {
-                    Ok(mut v) => {
-                        match lastnode.metadata().navalias() {
-                            Some(Navalias::Complex{name, value}) => {
-                                v.push(value);
-                                let full_pathbuf = validpath.pathbuf().join(v);
-                                // we need to account for the posibility that an alias has been found for multiple nodes.
-                                // The strategy we will use is to only replace a k/v pair if the value is shorter
-                                // in length. 
-                                match navaliasmap.get(name) {
-                                    Some(value) => {
-                                        if full_pathbuf.components().count() < value.components().count() {
-                                            navaliasmap.insert(name.to_owned(), full_pathbuf);
-                                        }
-                                    }
-                                    None => {
-                                        navaliasmap.insert(name.to_owned(), full_pathbuf);
-                                    } 
-                                }
-                            }
-                            Some(Navalias::Simple(name)) => {
-                                match lastnode.identity() {
-                                    NodeType::Simple(n) => v.push(n),
-                                    _ => panic!("Illegal combination of non Simple NodeType and Simple Navalias"),
-                                }
-                                let full_pathbuf = validpath.pathbuf().join(v);
-                                match navaliasmap.get(name) {
-                                    Some(value) => {
-                                        if full_pathbuf.components().count() < value.components().count() {
-                                            navaliasmap.insert(name.to_owned(), full_pathbuf);
-                                        }
-                                    }
-                                    None => {
-                                        navaliasmap.insert(name.to_owned(), full_pathbuf);
-                                    } 
-                                }
-                            }
-                            None => { panic!("lastnode.metadata.navalias is None");}
-                        }
-                    },
+                // the nodepath leading up to the last node may only be converted when 
+                // it consists of Simple nodes
+                let parent = match nodepath.to_pathbuf() {
+                    Ok(relpath) => validpath.pathbuf().join(relpath),
                     Err(e) => {
-                        report::shellerror("Unable to convert nodepath to pathbuf. skipping nodepath.", Some(e), verbose);
-                        continue
-                    } 
+                        match report {
+                            Some(verbose) => report::shellerror("process_navalias(...). Unable to convert nodepath to pathbuf. skipping nodepath.", Some(e), verbose),
+                            None => log::debug!("process_navalias(...). Unable to convert nodepath to pathbuf: {}", e),
+                        }
+                        continue;
+                    }
                 };
-                */
+                let own_name = match lastnode.identity() {
+                    NodeType::Simple(n) => Some(n.as_str()),
+                    _ => None,
+                };
+                process_navalias_entry(&parent, own_name, lastnode, &values, &mut navaliasmap, report);
             }
         }
     } 
     navaliasmap
 }
 
-// Takes a root validpath, a relative nodepath, and a lastnode. The validpath should come from the primary
-// search, the nodepath from find_rel, with the last node pop'ed off, and the lastnode is that node. Why?
-// This is done to avoid an error when we call nodepath.to_pathbuf() when the last node is a Regex, 
-// which one cannot convert from. We however, have a trick up our sleave, in that in this case, the author
-// of the template must supply a suitable way of identifying the last node via metadata, which should be
-// of type Navlalias::Complex. For example, the work directory would be [ navalias: cs work.$USER ]  
-fn process_navalias_entry(
+// Resolve the navaliases declared by the last node of `validpath` itself. The 
+// node's directory name is the last component of the path, regardless of the 
+// node's type.
+fn process_leaf_navalias(
     validpath: &ValidPath, 
-    nodepath: &NodePath, 
+    values: &BTreeMap<String, String>, 
+    navaliasmap: &mut NavaliasMap, 
+    report: Option<bool>
+) {
+    let leaf = match validpath.nodepath().leaf() {
+        Some(leaf) if leaf.metadata().has_navalias() => leaf,
+        _ => return,
+    };
+    if let Some(parent) = validpath.path().parent() {
+        let own_name = validpath.path().file_name().and_then(|name| name.to_str());
+        process_navalias_entry(parent, own_name, leaf, values, navaliasmap, report);
+    }
+}
+
+// Retrieve the values which navalias and env values may reference - the named 
+// captures, node names and varnames of the current location. 
+fn template_values(context: &PathContext) -> BTreeMap<String, String> {
+    let mut values = context.vars.clone();
+    values.extend(context.nodes.clone());
+    values.extend(context.captures.clone());
+    values
+}
+
+// Resolve the navaliases declared by `lastnode` and add them to the navaliasmap. 
+// `parent` is the directory containing the node, and `own_name` the name of the 
+// node's directory, if known. An alias without a value changes directory to the 
+// node itself, and so is skipped if the name of its directory is not known (eg a 
+// RegEx node beneath the current location). An alias with a value changes 
+// directory to the expanded value, relative to `parent`. Aliases which have 
+// already been resolved are only replaced by aliases which supersede them.
+fn process_navalias_entry(
+    parent: &Path, 
+    own_name: Option<&str>,
     lastnode: &Node, 
-    navaliasmap: &mut NavaliasMap
-) -> Result<(), JSPError> {
-
-    match nodepath.to_pathbuf() {
-        Ok(mut v) => {
-            match lastnode.metadata().navalias() {
-                Some(Navalias::Complex{name, value}) => {
-                    v.push(value);
-                    let full_pathbuf = validpath.pathbuf().join(v);
-                    // we need to account for the posibility that an alias has been found for multiple nodes.
-                    // The strategy we will use is to only replace a k/v pair if the value is shorter
-                    // in length. 
-                    match navaliasmap.get(name) {
-                        Some(value) => {
-                            if full_pathbuf.components().count() < value.components().count() {
-                                navaliasmap.insert(name.to_owned(), full_pathbuf);
-                            }
-                        }
-                        None => {
-                            navaliasmap.insert(name.to_owned(), full_pathbuf);
-                        } 
+    values: &BTreeMap<String, String>,
+    navaliasmap: &mut NavaliasMap,
+    report: Option<bool>
+) {
+    for navalias in lastnode.metadata().navaliases() {
+        let full_pathbuf = match (&navalias.value, own_name) {
+            (Some(value), _) => match navalias::expand_value(value, values) {
+                Ok(value) => parent.join(value),
+                Err(name) => {
+                    let msg = format!("navalias {} skipped. Unable to resolve ${}", navalias.name, name);
+                    match report {
+                        Some(verbose) => report::shellinfo(msg, verbose),
+                        None => log::debug!("process_navalias_entry(...). {}", msg),
                     }
+                    continue;
                 }
-                Some(Navalias::Simple(name)) => {
-                    match lastnode.identity() {
-                        NodeType::Simple(n) => v.push(n),
-                        _ => panic!("process_navalias_entry(...). Illegal combination of non Simple NodeType and Simple Navalias"),
-                    }
-                    let full_pathbuf = validpath.pathbuf().join(v);
-                    match navaliasmap.get(name) {
-                        Some(value) => {
-                            if full_pathbuf.components().count() < value.components().count() {
-                                navaliasmap.insert(name.to_owned(), full_pathbuf);
-                            }
-                        }
-                        None => {
-                            navaliasmap.insert(name.to_owned(), full_pathbuf);
-                        } 
-                    }
-                }
-                None => { panic!("process_navalias_entry(...). lastnode.metadata.navalias is None");}
+            },
+            (None, Some(name)) => parent.join(name),
+            (None, None) => {
+                log::debug!("process_navalias_entry(...). navalias {} of {} requires a value", navalias.name, lastnode.name());
+                continue;
             }
-        },
-        Err(e) => {
-            return Err(e);
-            //report::shellerror("Unable to convert nodepath to pathbuf. skipping nodepath.", Some(e), verbose);
-        } 
-    };
-    Ok(())
+        };
+        let supersedes = match navaliasmap.get(&navalias.name) {
+            Some((current, current_path)) => navalias.supersedes(&full_pathbuf, current, current_path),
+            None => true,
+        };
+        if supersedes {
+            navaliasmap.insert(navalias.name.clone(), (navalias.clone(), full_pathbuf));
+        }
+    }
 }
 
+/// Resolve the levelspec at the head of `terms` fuzzily, so that partial or 
+/// abbreviated levels (eg `dev.r.99`) are matched against the existing 
+/// locations. The levelspec of an address is resolved likewise. Terms which 
+/// begin with a path are returned untouched. See `fuzzy::resolve_levelspec`.
+pub fn fuzzy_terms(mut terms: Vec<String>, graph: &JGraph) -> Result<Vec<String>, JSPError> {
+    if terms.is_empty() {
+        return Ok(terms);
+    }
+    if let Some(address) = Address::parse(&terms[0]) {
+        let levelspec = fuzzy::resolve_levelspec(&address.levelspec, graph)?;
+        terms[0] = format!("{}{}", levelspec, &terms[0][address.levelspec.len()..]);
+    } else if !terms[0].contains('/') {
+        terms[0] = fuzzy::resolve_levelspec(&terms[0], graph)?;
+    }
+    Ok(terms)
+}
 
-pub fn gen_terms_from_strings(mut terms: Vec<String>) -> Result<Vec<SearchTerm>, JSPError> {
+/// Convert a levelspec, followed by zero or more `key:value` terms, into 
+/// SearchTerms. The levelspec is parsed according to the supplied levels, 
+/// and relative levels are taken from the environment.
+pub fn gen_terms_from_strings(mut terms: Vec<String>, levels: &Levels) -> Result<Vec<SearchTerm>, JSPError> {
 
     let lspec_term;
     if terms.is_empty() {
@@ -467,23 +777,7 @@ pub fn gen_terms_from_strings(mut terms: Vec<String>) -> Result<Vec<SearchTerm>,
         terms = tmp;
     }
     // convert spec term to searchterms
-    let ls = LevelSpec::new(&lspec_term[0])?;
-
-    let mut ls = ls.rel_to_abs(|level|{
-        match level {
-            LevelName::Show => env::var("DD_SHOW").ok(),
-            LevelName::Sequence => env::var("DD_SEQUENCE").ok(),
-            LevelName::Shot => env::var("DD_SHOT").ok(),
-        }
-    })?;
-
-    ls.set_upper();
-    let mut levelspec_terms = 
-        ls.to_vec_str()
-        .into_iter()
-        .enumerate()
-        .map(|(idx,x)| format!("{}:{}", constants::LEVELS[idx], x))
-        .collect::<Vec<String>>();
+    let mut levelspec_terms = levels.to_terms(&lspec_term[0])?;
     levelspec_terms.append(&mut terms);
 
     // fold over the input vector of Strings, discarding any Strings which cannot
@@ -499,91 +793,464 @@ pub fn gen_terms_from_strings(mut terms: Vec<String>) -> Result<Vec<SearchTerm>,
     Ok(terms)
 } 
 
+// Retrieve the values which the env exports of a node may reference. In addition 
+// to the template values, `$name` is the node's directory name, `$path` the path 
+// to the node, and `$levelspec` the levelspec of the current location. 
+fn env_values(values: &BTreeMap<String, String>, context: &PathContext, components: &[String]) -> BTreeMap<String, String> {
+    let mut values = values.clone();
+    if let Some(name) = components.last() {
+        values.insert("name".to_string(), name.clone());
+    }
+    values.insert("path".to_string(), components.iter().collect::<PathBuf>().to_string_lossy().to_string());
+    if let Some(ref levelspec) = context.levelspec {
+        values.insert("levelspec".to_string(), levelspec.clone());
+    }
+    values
+}
+
+// Load the env file of the node at `components`, given the path to the env file
+// relative to the node. Missing env files are skipped, and invalid ones reported
+// and skipped.
+fn load_envfile(envfile: &str, values: &BTreeMap<String, String>, components: &[String], verbose: bool) -> Option<EnvFile> {
+    let envfile = match navalias::expand_value(envfile, values) {
+        Ok(envfile) => envfile,
+        Err(missing) => {
+            report::shellwarning(format!("env file {} not loaded. Unable to resolve ${}", envfile, missing), verbose);
+            return None;
+        }
+    };
+    let file = components.iter().collect::<PathBuf>().join(envfile);
+    if !file.is_file() {
+        log::debug!("load_envfile(...) env file {:?} does not exist", file);
+        return None;
+    }
+    match EnvFile::from_file(file) {
+        Ok(envfile) => Some(envfile),
+        Err(e) => {
+            report::shellwarning(format!("env file not loaded. {}", e), verbose);
+            None
+        }
+    }
+}
+
 #[inline]
-fn process_go_success(validpath: &mut ValidPath, mut navalias_map: &mut NavaliasMap, myshell: Box<dyn ShellEnvManager>, verbose: bool) 
--> Result<(), JSPError>
+fn process_go_success(validpath: &ValidPath, navalias_map: &mut NavaliasMap, verbose: bool) 
+-> Result<GoPlan, JSPError>
 {
     log::info!("process_go_success(...)");
     
-    let components = validpath.pathbuf().components().map(|x| {
-        match x {
-            Component::RootDir => String::from("/"),
-            Component::Normal(level) => level.to_str().unwrap().to_string(),
-            Component::CurDir => String::from("."),
-            Component::ParentDir => String::from(".."),
-            Component::Prefix(_) => panic!("process_go_success(...). Component::Prefix in path not supported"),
-        }
-    }).collect::<VecDeque<String>>();
+    let components = pathcontext::path_components(validpath.path());
+    let context = PathContext::from_validpath(validpath);
+    let values = template_values(&context);
     
-    // set env vars 
-    let mut varnames: Vec<&str> = Vec::new();
+    // OUTPUT_PATH
+    let mut plan = GoPlan::new(validpath.pathbuf());
 
-    // generate string to clear previously cached variables
-    let cached = CachedEnvVars::new();
-    print!("{}", cached.clear(&myshell));
-    // generate code to export a variable
-    // TODO: make this part of the trait so that we can abstract over shell
-    {
+    // env files may not set the varnames of the template, which identify the location
+    let graph = validpath.nodepath().graph();
+    let template_varnames = graph.node_indices()
+                                 .filter_map(|idx| graph[idx].metadata().varname_ref())
+                                 .collect::<Vec<&str>>();
+
+    // set env vars 
+    let mut varnames: Vec<String> = Vec::new();
     for (idx, n) in validpath.nodepath().iter().enumerate() {
-        if n.metadata().has_varname() {
-            let varname = n.metadata().varname_ref().unwrap();
-            print!("{}", &myshell.set_env_var(varname, &components[idx]));
-            varnames.push(varname);
+        if let Some(varname) = n.metadata().varname_ref() {
+            plan.set_vars.insert(varname.to_string(), components[idx].clone());
+            varnames.push(varname.to_string());
+        }
+        // env files and env exports. Deeper nodes take precedence over their ancestors,
+        // and a node's env exports over its env file
+        if n.metadata().envfile().is_none() && n.metadata().env().is_empty() {
+            continue;
+        }
+        let values = env_values(&values, &context, &components[..=idx]);
+        if let Some(envfile) = n.metadata().envfile() {
+            if let Some(envfile) = load_envfile(envfile, &values, &components[..=idx], verbose) {
+                for (name, value) in envfile.vars() {
+                    if template_varnames.contains(&name.as_str()) {
+                        report::shellwarning(
+                            format!("{} not set by env file {}. It is a template varname", name, envfile.file().display()), 
+                            verbose
+                        );
+                        continue;
+                    }
+                    plan.set_vars.insert(name.clone(), value.clone());
+                    if !varnames.contains(name) {
+                        varnames.push(name.clone());
+                    }
+                }
+            }
+        }
+        for (name, value) in n.metadata().env() {
+            match navalias::expand_value(value, &values) {
+                Ok(value) => {
+                    plan.set_vars.insert(name.clone(), value);
+                    if !varnames.contains(name) {
+                        varnames.push(name.clone());
+                    }
+                }
+                Err(missing) => report::shellwarning(
+                    format!("{} not exported. Unable to resolve ${}", name, missing), verbose
+                ),
+            }
         }
-    }
     }
 
-    // OUTPUT_PATH
-    // Preserve the original output path before we start manipulating validpath
-    let output_path = validpath.pathbuf();
-
     // if we have variable names that we have set, we also need to preserve their names, so that
     // we can clear them out on subsequent runs. This solves the scenario where you navigate
     // deep into the tree, and then later navigate to a shallower level; you don't want the 
     // variables tracking levels deeper than the current depth to be set. 
     if !varnames.is_empty() {
-        print!("{}", &myshell.set_env_var(constants::JSP_TRACKING_VAR, varnames.join(":").as_str())) ;
+        plan.set_vars.insert(constants::JSP_TRACKING_VAR.to_string(), varnames.join(":"));
     } else {
-        print!("{}", &myshell.unset_env_var(constants::JSP_TRACKING_VAR));
-    }
-    // pop off a ValidPath containing just the last node, and shorten the validpath by 1
-    let last_validpath = validpath.pop()?;
-    // extract the last node from the ValidPath
-    let lastnode = last_validpath
-                    .nodepath()
-                    .leaf()
-                    .expect("process_go_success(...). Unable to get the leaf of the last node");
-    let relpath = NodePath::new(validpath.nodepath().graph());
-    if lastnode.metadata().has_navalias() {
-        // the validpath is the root path, the relpath is the path leading up to the last node, 
-        // validpath/relpath/lastnode
-        match process_navalias_entry( &validpath, &relpath, &lastnode, &mut navalias_map) {
-            Err(e) => {
-                report::shellerror("process_go_success(...). Unable to convert nodepath to pathbuf. skipping navalias.", 
-                Some(e), verbose);
-            }
-            _ => {}
-        };
+        plan.unset_vars.push(constants::JSP_TRACKING_VAR.to_string());
     }
+    // clear previously cached variables which are not being set again
+    let cached = CachedEnvVars::new();
+    let stale = cached.iter().filter(|v| !plan.set_vars.contains_key(*v)).map(|v| v.to_string()).collect::<Vec<String>>();
+    plan.unset_vars.extend(stale);
 
-    let mut varnames = Vec::new();
-    // iterate through cached aliases, clearing each alias
-    let cached = CachedAliases::new();
-    print!("{}", cached.clear(&myshell));
-    // iterate trhough the navaliases, setting each one
-    for (k,v) in navalias_map.into_iter() {
-        print!("{}", &myshell.set_alias(k,v));
-        varnames.push(k.as_str());
+    process_leaf_navalias(validpath, &values, navalias_map, Some(verbose));
+
+    // set the navaliases, and export each as a JSP_NAV_<NAME> environment variable for 
+    // tools which cannot use aliases. Clear cached aliases which are not being set again.
+    for (name, (_, path)) in navalias_map.drain() {
+        plan.set_vars.insert(run::nav_env_var(&name), path.to_string_lossy().to_string());
+        plan.set_aliases.insert(name, path);
     }
+    let cached = CachedAliases::new();
+    let stale = cached.iter().filter(|a| !plan.set_aliases.contains_key(*a)).map(|a| a.to_string()).collect::<Vec<String>>();
+    plan.unset_vars.extend(stale.iter().map(|a| run::nav_env_var(a)));
+    plan.clear_aliases.extend(stale);
+
     // Reset the JSP_ALIAS_NAMES env var which tracks the previously set aliases
-    if !varnames.is_empty() {
-        print!("{}", &myshell.set_env_var(constants::JSP_ALIAS_NAMES, varnames.join(":").as_str())) ;
+    if !plan.set_aliases.is_empty() {
+        let names = plan.set_aliases.keys().map(|k| k.as_str()).collect::<Vec<&str>>().join(":");
+        plan.set_vars.insert(constants::JSP_ALIAS_NAMES.to_string(), names);
     } else {
-        print!("{}", &myshell.unset_env_var(constants::JSP_ALIAS_NAMES));
+        plan.unset_vars.push(constants::JSP_ALIAS_NAMES.to_string());
     }
 
-    let target_dir = output_path.as_os_str().to_str().unwrap();
-    println!("cd {};", target_dir);
-    println!("echo Changed Directory To: {}\n", target_dir);
-    Ok(())
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod go_script_test {
+    use super::*;
+    use crate::{jspnode, EntryType, Regexp, testutil::{graph_to, TempDir}};
+    use std::{fs, os::unix::fs::PermissionsExt, process::Command};
+
+    const HOSTILE: &str = "x'; touch pwned; echo \"$(touch pwned)\" `touch pwned` !! \\";
+
+    // /dd/<anything>/tools, where <anything> sets DD_PROJ and tools sets the cdtools alias
+    fn build_permissive_graph() -> JGraph {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let proj = graph.add_node(jspnode!("proj", r"^.+$", "varname" => "DD_PROJ"));
+        let tools = graph.add_node(jspnode!("tools", "navalias" => "cdtools"));
+        graph.extend_with_edges(&[(root, dd), (dd, proj), (proj, tools)]);
+        graph
+    }
+
+    fn hostile_script(shell: &str) -> String {
+        let graph = build_permissive_graph();
+        let path = format!("/dd/{}", HOSTILE);
+        let (_, script) = go_script(vec![path], Some(shell.to_string()), &graph, true, false, false).unwrap();
+        script
+    }
+
+    #[test]
+    fn hostile_values_are_quoted() {
+        let expected = [
+            ("bash", 
+                r#"export DD_PROJ='x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` !! \';"#,
+                r#"cd '/dd/x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` !! \';"#),
+            ("zsh", 
+                r#"export DD_PROJ='x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` !! \';"#,
+                r#"cd '/dd/x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` !! \';"#),
+            ("fish", 
+                r#"set -gx DD_PROJ 'x\'; touch pwned; echo "$(touch pwned)" `touch pwned` !! \\';"#,
+                r#"cd '/dd/x\'; touch pwned; echo "$(touch pwned)" `touch pwned` !! \\';"#),
+            ("tcsh", 
+                r#"setenv DD_PROJ 'x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` '\!''\!' \';"#,
+                r#"cd '/dd/x'\''; touch pwned; echo "$(touch pwned)" `touch pwned` '\!''\!' \';"#),
+        ];
+        for (name, set_var, cd) in &expected {
+            let script = hostile_script(name);
+            assert!(script.contains(set_var), "{}: {}", name, script);
+            assert!(script.contains(cd), "{}: {}", name, script);
+            assert!(script.contains("cdtools"), "{}: {}", name, script);
+        }
+    }
+
+    #[test]
+    fn can_build_plan() {
+        let graph = build_permissive_graph();
+        let (_, plan) = go_plan(vec!["/dd/myproj".to_string()], &graph, true, false, false).unwrap();
+        assert_eq!(plan.target, PathBuf::from("/dd/myproj"));
+        assert_eq!(plan.set_vars.get("DD_PROJ").map(|v| v.as_str()), Some("myproj"));
+        assert_eq!(plan.set_vars.get(constants::JSP_TRACKING_VAR).map(|v| v.as_str()), Some("DD_PROJ"));
+        assert_eq!(plan.set_aliases.get("cdtools"), Some(&PathBuf::from("/dd/myproj/tools")));
+        assert!(!plan.unset_vars.contains(&"DD_PROJ".to_string()));
+    }
+
+    #[test]
+    fn navaliases_are_exported_as_env_vars() {
+        let graph = build_permissive_graph();
+        let (_, plan) = go_plan(vec!["/dd/myproj".to_string()], &graph, true, false, false).unwrap();
+        assert_eq!(plan.set_vars.get("JSP_NAV_CDTOOLS").map(|v| v.as_str()), Some("/dd/myproj/tools"));
+    }
+
+    #[test]
+    fn navaliases_resolve_by_priority_and_expand_values() {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let mut proj = jspnode!("proj", r"^(?P<proj>[a-z]+)$", "navalias" => "cdproj");
+        proj.metadata_mut().add_navalias(Navalias::new_complex("cdlogs", "$proj/logs/$DATE"));
+        let proj = graph.add_node(proj);
+        let mut tools = jspnode!("tools", "navalias" => "cdtools");
+        tools.metadata_mut().add_navalias(Navalias::new_simple("cdhome").with_priority(1));
+        let tools = graph.add_node(tools);
+        let home = graph.add_node(jspnode!("home", "navalias" => "cdhome"));
+        graph.extend_with_edges(&[(root, dd), (dd, proj), (proj, home), (proj, tools)]);
+
+        let (_, plan) = go_plan(vec!["/dd/myproj".to_string()], &graph, true, false, false).unwrap();
+        // a simple navalias on a regex leaf resolves to the leaf
+        assert_eq!(plan.set_aliases.get("cdproj"), Some(&PathBuf::from("/dd/myproj")));
+        // the higher priority wins, although its path is no shorter
+        assert_eq!(plan.set_aliases.get("cdhome"), Some(&PathBuf::from("/dd/myproj/tools")));
+        let logs = plan.set_aliases.get("cdlogs").unwrap().to_string_lossy().to_string();
+        assert!(logs.starts_with("/dd/myproj/logs/"), "{}", logs);
+        assert!(!logs.contains('$'), "{}", logs);
+    }
+
+    #[test]
+    fn can_plan_address_with_path() {
+        let graph = crate::graph::testdata::build_graph();
+        let (_, plan) = go_plan(vec!["dev01:assetdev/BOBCHAR".to_string()], &graph, false, false, false).unwrap();
+        assert_eq!(plan.target, PathBuf::from("/dd/shows/DEV01/ASSETDEV/BOBCHAR"));
+        assert_eq!(plan.set_vars.get("DD_SHOW").map(|v| v.as_str()), Some("DEV01"));
+        assert_eq!(plan.set_vars.get("DD_SHOT").map(|v| v.as_str()), Some("BOBCHAR"));
+    }
+
+    #[test]
+    fn env_exports_are_set_and_tracked() {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let mut proj = jspnode!("proj", r"^(?P<code>[a-z]+)[0-9]*$", "varname" => "DD_PROJ", "env" => "DD_PROJ_ROOT=$path");
+        proj.metadata_mut().add_env("DD_PROJ_CODE", "${code}_$name");
+        let proj = graph.add_node(proj);
+        let tools = graph.add_node(jspnode!("tools", "env" => "DD_PROJ_ROOT=$path"));
+        graph.extend_with_edges(&[(root, dd), (dd, proj), (proj, tools)]);
+
+        let (_, plan) = go_plan(vec!["/dd/myproj01".to_string()], &graph, true, false, false).unwrap();
+        assert_eq!(plan.set_vars.get("DD_PROJ_ROOT").map(|v| v.as_str()), Some("/dd/myproj01"));
+        assert_eq!(plan.set_vars.get("DD_PROJ_CODE").map(|v| v.as_str()), Some("myproj_myproj01"));
+        assert_eq!(
+            plan.set_vars.get(constants::JSP_TRACKING_VAR).map(|v| v.as_str()), 
+            Some("DD_PROJ:DD_PROJ_ROOT:DD_PROJ_CODE")
+        );
+
+        // deeper nodes take precedence
+        let (_, plan) = go_plan(vec!["/dd/myproj01/tools".to_string()], &graph, true, false, false).unwrap();
+        assert_eq!(plan.set_vars.get("DD_PROJ_ROOT").map(|v| v.as_str()), Some("/dd/myproj01/tools"));
+    }
+
+    // <dir>/<proj>/tools, for testing locations which do not exist
+    fn build_tmp_graph(dir: &TempDir) -> JGraph {
+        let (mut graph, parent) = graph_to(dir.path());
+        let proj = graph.add_node(jspnode!("proj", r"^proj[0-9]+$"));
+        let tools = graph.add_node(jspnode!("tools"));
+        graph.extend_with_edges(&[(parent, proj), (proj, tools)]);
+        graph
+    }
+
+    #[test]
+    fn missing_target_navigates_to_ancestor() {
+        let dir = TempDir::new("missing_ancestor");
+        let graph = build_tmp_graph(&dir);
+        let requested = dir.join("proj01/tools");
+        let (validpath, plan) = go_plan(vec![requested.to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let (validpath, plan) = resolve_missing_target(validpath, plan, &graph, &MissingTarget::Ancestor, false).unwrap();
+        assert_eq!(validpath.path(), dir.path());
+        assert_eq!(plan.target, dir.path().to_path_buf());
+        assert_eq!(plan.outcome, GoOutcome::Ancestor{ requested });
+    }
+
+    #[test]
+    fn missing_target_may_fail() {
+        let dir = TempDir::new("missing_fail");
+        let graph = build_tmp_graph(&dir);
+        let requested = dir.join("proj01/tools").to_string_lossy().to_string();
+        let (validpath, plan) = go_plan(vec![requested.clone()], &graph, true, false, false).unwrap();
+        assert_eq!(
+            resolve_missing_target(validpath, plan, &graph, &MissingTarget::Fail, false).err(),
+            Some(JSPError::NonExtantPathError(PathBuf::from(requested)))
+        );
+    }
+
+    // a stand-in for jspmk, running `script` with jspmk's arguments
+    fn fake_jspmk(dir: &TempDir, script: &str) -> PathBuf {
+        let jspmk = dir.join("jspmk");
+        fs::write(&jspmk, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&jspmk, fs::Permissions::from_mode(0o755)).unwrap();
+        jspmk
+    }
+
+    #[test]
+    fn missing_target_may_be_created_by_jspmk() {
+        let dir = TempDir::new("missing_create");
+        let graph = build_tmp_graph(&dir);
+        // record the arguments, and create the location, which is the last of them
+        let jspmk = fake_jspmk(&dir, r#"echo "$@" > "$(dirname "$0")/args"; for last; do :; done; mkdir -p "$last""#);
+        let requested = dir.join("proj01/tools");
+        let (validpath, plan) = go_plan(vec![requested.to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let missing = MissingTarget::Create{ jspmk, ignore_volume: false };
+        let (validpath, plan) = resolve_missing_target(validpath, plan, &graph, &missing, false).unwrap();
+        assert_eq!(validpath.path(), requested.as_path());
+        assert_eq!(plan.target, requested);
+        assert_eq!(plan.outcome, GoOutcome::Created);
+        assert!(requested.is_dir());
+        assert_eq!(
+            fs::read_to_string(dir.join("args")).unwrap(), 
+            format!("--fullpath --auto {}\n", requested.display())
+        );
+    }
+
+    #[test]
+    fn failing_to_create_missing_target_is_err() {
+        let dir = TempDir::new("missing_create_fail");
+        let graph = build_tmp_graph(&dir);
+        let requested = dir.join("proj01/tools");
+        let (validpath, plan) = go_plan(vec![requested.to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let missing = MissingTarget::Create{ jspmk: fake_jspmk(&dir, "exit 1"), ignore_volume: false };
+        assert_eq!(
+            resolve_missing_target(validpath, plan, &graph, &missing, false).err(),
+            Some(JSPError::MkdirFailure(requested.display().to_string()))
+        );
+        assert!(!requested.exists());
+    }
+
+    #[test]
+    fn env_files_are_merged_from_root_to_leaf() {
+        let tmp = TempDir::new("envfile");
+        let (mut graph, parent) = graph_to(tmp.path());
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[0-9]*$", "envfile" => "etc/show.env"));
+        let shot = graph.add_node(jspnode!("shot", r"^[0-9]+$", "envfile" => "$name.env", "env" => "FPS=30"));
+        graph.extend_with_edges(&[(parent, show), (show, shot)]);
+
+        let dir = tmp.join("DEV01");
+        tmp.mkdirs(&["DEV01/etc", "DEV01/0001"]);
+        fs::write(dir.join("etc/show.env"), "OCIO=/show/config.ocio\nFPS=24\nRES=\"2048 1080\"\n").unwrap();
+        fs::write(dir.join("0001/0001.env"), "RES=4096x2160\nBAD LINE\n").unwrap();
+
+        let (_, show_plan) = go_plan(vec![dir.to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let (_, shot_plan) = go_plan(vec![dir.join("0001").to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+
+        assert_eq!(show_plan.set_vars.get("OCIO").map(|v| v.as_str()), Some("/show/config.ocio"));
+        assert_eq!(show_plan.set_vars.get("RES").map(|v| v.as_str()), Some("2048 1080"));
+        assert_eq!(show_plan.set_vars.get(constants::JSP_TRACKING_VAR).map(|v| v.as_str()), Some("OCIO:FPS:RES"));
+        // the shot's env file is invalid, so is skipped. Its env exports still apply
+        assert_eq!(shot_plan.set_vars.get("RES").map(|v| v.as_str()), Some("2048 1080"));
+        assert_eq!(shot_plan.set_vars.get("FPS").map(|v| v.as_str()), Some("30"));
+    }
+
+    #[test]
+    fn env_files_may_not_set_template_varnames() {
+        let tmp = TempDir::new("envfile_varnames");
+        let (mut graph, parent) = graph_to(tmp.path());
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[0-9]*$", "varname" => "DD_SHOW", "envfile" => "show.env"));
+        let shot = graph.add_node(jspnode!("shot", r"^[0-9]+$", "varname" => "DD_SHOT"));
+        graph.extend_with_edges(&[(parent, show), (show, shot)]);
+
+        let dir = tmp.join("DEV01");
+        tmp.mkdirs(&["DEV01/0001"]);
+        fs::write(dir.join("show.env"), "DD_SHOW=OTHER\nDD_SHOT=9999\nFPS=24\n").unwrap();
+
+        let (_, show_plan) = go_plan(vec![dir.to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let (_, shot_plan) = go_plan(vec![dir.join("0001").to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+
+        assert_eq!(show_plan.set_vars.get("DD_SHOW").map(|v| v.as_str()), Some("DEV01"));
+        // varnames of deeper levels are not set either, as they would not be cleared 
+        assert_eq!(show_plan.set_vars.get("DD_SHOT"), None);
+        assert_eq!(show_plan.set_vars.get(constants::JSP_TRACKING_VAR).map(|v| v.as_str()), Some("DD_SHOW:FPS"));
+        assert_eq!(shot_plan.set_vars.get("DD_SHOT").map(|v| v.as_str()), Some("0001"));
+        assert_eq!(shot_plan.set_vars.get("FPS").map(|v| v.as_str()), Some("24"));
+    }
+
+    #[test]
+    fn existing_target_is_untouched() {
+        let dir = TempDir::new("existing");
+        let graph = build_tmp_graph(&dir);
+        let (validpath, plan) = go_plan(vec![dir.path().to_string_lossy().to_string()], &graph, true, false, false).unwrap();
+        let (_, resolved) = resolve_missing_target(validpath, plan.clone(), &graph, &MissingTarget::Fail, false).unwrap();
+        assert_eq!(resolved, plan);
+        assert_eq!(resolved.outcome, GoOutcome::Exists);
+    }
+
+    // the go script for <dir>/<HOSTILE>, which is created so that the script runs
+    // to completion
+    fn hostile_script_in(dir: &TempDir, shell: &str) -> String {
+        let (mut graph, parent) = graph_to(dir.path());
+        let proj = graph.add_node(jspnode!("proj", r"^.+$", "varname" => "DD_PROJ"));
+        let tools = graph.add_node(jspnode!("tools", "navalias" => "cdtools"));
+        graph.extend_with_edges(&[(parent, proj), (proj, tools)]);
+        let target = dir.join(HOSTILE);
+        fs::create_dir(&target).unwrap();
+        let (_, script) = go_script(
+            vec![target.to_string_lossy().to_string()], Some(shell.to_string()), &graph, true, false, false
+        ).unwrap();
+        script
+    }
+
+    // Evaluate the go script in `shell`, as its jspgo wrapper would, by running `args` 
+    // followed by `command`, which evaluates go.script and prints DD_PROJ. The script
+    // must not run any commands, and must set DD_PROJ verbatim. Panics if the shell 
+    // cannot be run.
+    fn assert_inert(shell: &str, args: &[&str], command: &str) {
+        let dir = TempDir::new(&format!("inert_{}", shell));
+        let script = hostile_script_in(&dir, shell);
+        fs::write(dir.join("go.script"), &script).unwrap();
+
+        let output = Command::new(shell)
+                        .args(args)
+                        .arg(command)
+                        .current_dir(dir.path())
+                        .output()
+                        .unwrap_or_else(|e| panic!("unable to run {}: {}", shell, e));
+
+        for pwned in &[dir.join("pwned"), dir.join(HOSTILE).join("pwned")] {
+            assert!(!pwned.exists(), "{} script executed a command: {}", shell, script);
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}: {}", shell, String::from_utf8_lossy(&output.stderr));
+        assert!(stdout.ends_with(&format!("{}\n", HOSTILE)), "{}: {}", shell, stdout);
+    }
+
+    #[test]
+    fn hostile_values_are_inert_in_bash() {
+        assert_inert("bash", &["-c"], r#"eval "$(cat go.script)"; printenv DD_PROJ"#);
+    }
+
+    #[test]
+    #[ignore = "requires zsh. run with --ignored where it is installed"]
+    fn hostile_values_are_inert_in_zsh() {
+        assert_inert("zsh", &["-f", "-c"], r#"eval "$(cat go.script)"; printenv DD_PROJ"#);
+    }
+
+    #[test]
+    #[ignore = "requires fish. run with --ignored where it is installed"]
+    fn hostile_values_are_inert_in_fish() {
+        assert_inert("fish", &["-c"], r#"eval (string join \n -- (cat go.script)); printenv DD_PROJ"#);
+    }
+
+    #[test]
+    #[ignore = "requires tcsh. run with --ignored where it is installed"]
+    fn hostile_values_are_inert_in_tcsh() {
+        assert_inert("tcsh", &["-f", "-c"], "source go.script; printenv DD_PROJ");
+    }
 }
@@ -0,0 +1,346 @@
+//! Template aware completion of partial paths, levelspecs, and `key:value`
+//! terms. Completions are drawn from the template - the names of Simple
+//! children and the names and patterns of RegEx children - as well as from
+//! the directories on disk which satisfy the RegEx children.
+use crate::{
+    cli,
+    diskutils,
+    find_from,
+    validate_path,
+    JGraph,
+    JSPError,
+    Levels,
+    NIndex,
+    NodeType,
+};
+use petgraph::{visit::IntoNodeReferences, Direction::Outgoing};
+use serde::Serialize;
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The source of a Completion
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    /// The name of a Simple child in the template
+    Simple,
+    /// The name of a RegEx child in the template, along with its pattern.
+    /// This is informational, and is not meant to be inserted by the shell.
+    Pattern,
+    /// An existing directory on disk matching a RegEx child
+    Disk,
+    /// The `key:` of a RegEx node in the template
+    Key,
+}
+
+/// A single completion candidate
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct Completion {
+    /// The completed input
+    pub value: String,
+    /// The source of the completion
+    pub kind: CompletionKind,
+    /// The name of the template node which the completion corresponds to
+    pub node: String,
+    /// The pattern of the template node, if it is a RegEx node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+impl Completion {
+    // new up a Completion
+    fn new<S: Into<String>>(value: S, kind: CompletionKind, node: &str, pattern: Option<&str>) -> Self {
+        Self {
+            value: value.into(),
+            kind,
+            node: node.to_string(),
+            pattern: pattern.map(|p| p.to_string()),
+        }
+    }
+
+    /// Should the completion be inserted by the shell?
+    pub fn is_insertable(&self) -> bool {
+        self.kind != CompletionKind::Pattern
+    }
+}
+
+/// Complete the supplied input, given the words which precede it on the
+/// command line. The input may be:
+///
+/// * a partial path (anything containing a `/`), whose final component is completed
+/// * a partial levelspec (eg `dev01.r`), if there are no preceding words
+/// * a partial key (eg `wo`), which is completed to `work:`
+/// * a partial `key:value` term (eg `work:jg`), whose value is completed from disk
+///
+/// # Parameters
+///
+/// * `input` - The partial input to complete
+/// * `preceding` - The preceding words. The first is expected to be a levelspec.
+/// * `graph` - Reference to the JGraph
+///
+/// # Returns
+/// Ok wrapping a vector of Completions if successful. Otherwise a JSPError if the
+/// input preceding the partial entry is not valid.
+pub fn complete(input: &str, preceding: &[String], graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    log::info!("complete(input: {}, preceding: {:?}, graph)", input, preceding);
+    if input.contains('/') {
+        complete_path(input, graph)
+    } else if let Some(idx) = input.find(':') {
+        complete_value(&input[..idx], &input[idx+1..], preceding, graph)
+    } else if preceding.is_empty() {
+        complete_levelspec(input, graph)
+    } else {
+        complete_key(input, preceding, graph)
+    }
+}
+
+/// Complete the last word of a command line, as supplied by a shell completion
+/// script. The first word, which is the command being completed, is ignored.
+/// If the line ends in whitespace, an empty word is completed.
+///
+/// # Parameters
+///
+/// * `line` - The command line, up to the cursor
+/// * `graph` - Reference to the JGraph
+pub fn complete_line(line: &str, graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    let mut words = line.split_whitespace().skip(1).map(|w| w.to_string()).collect::<Vec<String>>();
+    if line.is_empty() || line.ends_with(char::is_whitespace) {
+        words.push(String::new());
+    }
+    let input = words.pop().unwrap_or_default();
+    complete(&input, &words, graph)
+}
+
+/// Complete the final component of a partial path.
+pub fn complete_path(input: &str, graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    // the input up to and including the last '/' is preserved as typed
+    let split = input.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (typed, prefix) = input.split_at(split);
+    let parent = if typed.is_empty() { "." } else { typed };
+    let parent = diskutils::convert_relative_pathbuf_to_absolute(PathBuf::from(parent))?;
+
+    let nodepath = validate_path(&parent, graph)?;
+    // the path extends beyond the template; there is nothing to complete
+    if nodepath.len() < parent.components().count() {
+        return Ok(Vec::new());
+    }
+    let parent_idx = match nodepath.nindex() {
+        Some(idx) => idx,
+        None => return Ok(Vec::new()),
+    };
+    let children = match nodepath.neighbors() {
+        Some(neighbors) => neighbors.collect::<Vec<NIndex>>(),
+        None => Vec::new(),
+    };
+    log::debug!("complete_path(...) parent {:?} {:?} children: {:?}", parent, parent_idx, children);
+
+    Ok(child_completions(&children, &parent, graph, |name| {
+        if name.starts_with(prefix) { Some(format!("{}{}/", typed, name)) } else { None }
+    }))
+}
+
+/// Complete the final level of a partial levelspec. Levels are matched
+/// case insensitively.
+pub fn complete_levelspec(input: &str, graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    let levels = Levels::of(graph);
+    let mut segments = input.split('.').collect::<Vec<&str>>();
+    let partial = segments.pop().unwrap_or("").to_uppercase();
+    let depth = segments.len();
+    let level = match levels.name(depth) {
+        Some(level) => level,
+        None => return Ok(Vec::new()),
+    };
+    let typed = if segments.is_empty() { String::new() } else { format!("{}.", segments.join(".")) };
+
+    let (base_dir, base_idx) = if segments.is_empty() {
+        root(graph)?
+    } else {
+        let validpath = cli::validpath_from_terms(vec![segments.join(".")], graph, false, false, false)?;
+        let idx = validpath.nodepath().nindex().ok_or_else(|| JSPError::FindFailure(input.to_string()))?;
+        (validpath.pathbuf(), idx)
+    };
+
+    let (dir, target) = resolve_key(level, &base_dir, base_idx, graph)?;
+    Ok(disk_completions(target, &dir, graph, |name, _| {
+        if name.to_uppercase().starts_with(&partial) { Some(format!("{}{}", typed, name)) } else { None }
+    }))
+}
+
+/// Complete a partial key, given the preceding words, to one of the names of the
+/// RegEx nodes beneath the node identified by the preceding words.
+pub fn complete_key(input: &str, preceding: &[String], graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    let (_, base_idx) = base(preceding, graph)?;
+    let levels = Levels::of(graph);
+    let mut seen = HashSet::new();
+    let mut completions = Vec::new();
+    let mut stack = vec![base_idx];
+    let mut visited = HashSet::new();
+
+    while let Some(idx) = stack.pop() {
+        if !visited.insert(idx) { continue; }
+        for child in graph.neighbors_directed(idx, Outgoing) {
+            if let NodeType::RegEx{name, pattern, ..} = graph[child].identity() {
+                if name.starts_with(input) && !levels.contains(name) && seen.insert(name.clone()) {
+                    completions.push(Completion::new(format!("{}:", name), CompletionKind::Key, name, Some(pattern.as_str())));
+                }
+            }
+            stack.push(child);
+        }
+    }
+    completions.sort_by(|a, b| a.value.cmp(&b.value));
+    Ok(completions)
+}
+
+/// Complete the value of a partial `key:value` term, given the preceding words, from
+/// the directories on disk matching the RegEx node named key. If the node's pattern
+/// has a named capture matching the key, the value of the capture is used.
+pub fn complete_value(key: &str, input: &str, preceding: &[String], graph: &JGraph) -> Result<Vec<Completion>, JSPError> {
+    let (base_dir, base_idx) = base(preceding, graph)?;
+    let (dir, target) = resolve_key(key, &base_dir, base_idx, graph)?;
+    Ok(disk_completions(target, &dir, graph, |name, captures| {
+        let value = captures.unwrap_or(name);
+        if value.starts_with(input) { Some(format!("{}:{}", key, value)) } else { None }
+    }))
+}
+
+// retrieve the root path and index
+pub(crate) fn root(graph: &JGraph) -> Result<(PathBuf, NIndex), JSPError> {
+    let idx = graph.node_references().next().ok_or_else(|| JSPError::FindFailure("empty graph".to_string()))?.0;
+    Ok((PathBuf::from("/"), idx))
+}
+
+// retrieve the path and index identified by the preceding words
+fn base(preceding: &[String], graph: &JGraph) -> Result<(PathBuf, NIndex), JSPError> {
+    if preceding.is_empty() {
+        return root(graph);
+    }
+    let validpath = cli::validpath_from_terms(preceding.to_vec(), graph, false, false, false)?;
+    let idx = validpath.nodepath().nindex().ok_or_else(|| JSPError::FindFailure(preceding.join(" ")))?;
+    Ok((validpath.pathbuf(), idx))
+}
+
+// find the RegEx node named key beneath base_idx, returning the directory which
+// contains it, along with its index
+pub(crate) fn resolve_key(key: &str, base_dir: &Path, base_idx: NIndex, graph: &JGraph) -> Result<(PathBuf, NIndex), JSPError> {
+    let rel = find_from(VecDeque::from(vec![key.to_string()]), base_idx, graph)?;
+    let target = rel.nindex().ok_or_else(|| JSPError::FindFailure(key.to_string()))?;
+    // every node leading up to the target is a Simple node
+    let mut dir = base_dir.to_path_buf();
+    for idx in &rel.nodes[..rel.nodes.len() - 1] {
+        dir.push(graph[*idx].name());
+    }
+    Ok((dir, target))
+}
+
+// generate completions for the supplied children of dir. `format` returns the completed
+// value given the name of the entry, or None if the entry should be skipped.
+fn child_completions<F>(children: &[NIndex], dir: &Path, graph: &JGraph, format: F) -> Vec<Completion>
+where
+    F: Fn(&str) -> Option<String>
+{
+    let mut completions = Vec::new();
+    let mut disk = Vec::new();
+    for idx in children {
+        let node = &graph[*idx];
+        match node.identity() {
+            NodeType::Simple(name) => {
+                if let Some(value) = format(name) {
+                    completions.push(Completion::new(value, CompletionKind::Simple, name, None));
+                }
+            }
+            NodeType::RegEx{name, pattern, ..} => {
+                completions.push(Completion::new(name.as_str(), CompletionKind::Pattern, name, Some(pattern.as_str())));
+                disk.append(&mut disk_completions(*idx, dir, graph, |entry, _| format(entry)));
+            }
+            _ => (),
+        }
+    }
+    for completion in disk {
+        if !completions.iter().any(|c| c.value == completion.value) {
+            completions.push(completion);
+        }
+    }
+    completions
+}
+
+// generate completions from the directories in dir matching the RegEx node. `format` is
+// passed the name of the directory, and the value of the named capture matching the
+// node's name, if there is one.
+pub(crate) fn disk_completions<F>(idx: NIndex, dir: &Path, graph: &JGraph, format: F) -> Vec<Completion>
+where
+    F: Fn(&str, Option<&str>) -> Option<String>
+{
+    let (name, pattern, exclude) = match graph[idx].identity() {
+        NodeType::RegEx{name, pattern, exclude} => (name, pattern, exclude),
+        _ => return Vec::new(),
+    };
+    let mut entries = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir.filter_map(|e| e.ok())
+                                .filter(|e| e.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+                                .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+                                .collect::<Vec<String>>(),
+        Err(_) => return Vec::new(),
+    };
+    entries.sort();
+
+    entries.iter().filter_map(|entry| {
+        if !pattern.is_match(entry) { return None; }
+        if let Some(exclude) = exclude {
+            if exclude.is_match(entry) { return None; }
+        }
+        let capture = pattern.captures(entry)
+                             .and_then(|caps| caps.name(name).map(|m| m.as_str().to_string()));
+        format(entry, capture.as_ref().map(|c| c.as_str()))
+            .map(|value| Completion::new(value, CompletionKind::Disk, name, Some(pattern.as_str())))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph;
+
+    fn values(completions: &[Completion]) -> Vec<&str> {
+        completions.iter().filter(|c| c.is_insertable()).map(|c| c.value.as_str()).collect()
+    }
+
+    #[test]
+    fn can_complete_path_from_template() {
+        let graph = build_graph();
+        let completions = complete("/dd/shows/DEV01/RD/9999/u", &[], &graph).unwrap();
+        assert_eq!(values(&completions), vec!["/dd/shows/DEV01/RD/9999/user/"]);
+    }
+
+    #[test]
+    fn path_completion_includes_patterns() {
+        let graph = build_graph();
+        let completions = complete("/dd/shows/DEV01/", &[], &graph).unwrap();
+        assert!(completions.iter().any(|c| c.kind == CompletionKind::Pattern && c.node == "sequence"));
+        assert!(values(&completions).contains(&"/dd/shows/DEV01/SHARED/"));
+    }
+
+    #[test]
+    fn can_complete_key() {
+        let graph = build_graph();
+        let completions = complete("wo", &["DEV01.RD.9999".to_string()], &graph).unwrap();
+        assert_eq!(values(&completions), vec!["work:"]);
+    }
+
+    #[test]
+    fn can_complete_line() {
+        let graph = build_graph();
+        let completions = complete_line("jspgo DEV01.RD.9999 wo", &graph).unwrap();
+        assert_eq!(values(&completions), vec!["work:"]);
+        let completions = complete_line("jspgo DEV01.RD.9999 ", &graph).unwrap();
+        assert!(completions.iter().any(|c| c.kind == CompletionKind::Key && c.value == "work:"));
+    }
+
+    #[test]
+    fn invalid_path_is_err() {
+        let graph = build_graph();
+        assert!(complete("/dd/shows/DEV01/foo/", &[], &graph).is_err());
+    }
+}
@@ -11,4 +11,18 @@ pub const LEVELS:        &[&str] = &["show", "sequence", "shot"];
 pub const JSP_SHOWS_ROOT_ENVVAR: &str = "DD_SHOWS_ROOT";
 pub const JSP_SHOW_ENVVAR:       &str = "DD_SHOW";
 pub const JSP_SEQUENCE_ENVVAR:   &str = "DD_SEQUENCE";
-pub const JSP_SHOT_ENVVAR:       &str = "DD_SHOT";
\ No newline at end of file
+pub const JSP_SHOT_ENVVAR:       &str = "DD_SHOT";
+
+// exit codes reported by jsp, so that the shell wrappers can tell failures apart
+pub const EXIT_INTERNAL_ERROR: i32 = 1;
+pub const EXIT_NOT_FOUND:      i32 = 2;
+pub const EXIT_INVALID:        i32 = 3;
+
+pub const JSP_CACHE_DIR:         &str = "JSP_CACHE_DIR";
+pub const JSP_PROMPT_FORMAT:     &str = "JSP_PROMPT_FORMAT";
+pub const JSP_HISTORY:           &str = "JSP_HISTORY";
+pub const JSP_HISTORY_FILE:      &str = ".jsp_history";
+pub const JSP_HISTORY_MAX:       usize = 200;
+pub const JSP_BOOKMARKS:         &str = "JSP_BOOKMARKS";
+pub const JSP_BOOKMARKS_FILE:    &str = ".jsp_bookmarks";
+pub const JSP_SHARED_BOOKMARKS:  &str = "bookmarks.json";
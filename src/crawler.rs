@@ -0,0 +1,169 @@
+//! Crawl a directory tree on disk, validating every directory against the
+//! JGraph. The crawl proceeds a level at a time, with the directories at each
+//! level shared out amongst a pool of scoped threads.
+//!
+//! Directories which fail to validate are reported, but not descended into.
+//! Nor are directories whose template node has no children, as there is
+//! nothing beneath them for the template to police.
+use crate::{validate_path, JGraph, JSPError};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// The outcome of validating a single directory during a crawl.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CrawlEntry {
+    /// The directory
+    pub path: PathBuf,
+    /// The reason the directory failed to validate, if it did
+    pub failure: Option<JSPError>,
+}
+
+impl CrawlEntry {
+    /// Did the directory validate?
+    pub fn is_valid(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Crawl the directory tree rooted at `root`, validating each directory using
+/// all of the available cores.
+///
+/// # Parameters
+///
+/// * `root` - The absolute path to the directory to start crawling from
+/// * `graph` - Reference to the JGraph
+///
+/// # Returns
+/// Ok wrapping a vector of CrawlEntry, sorted by path, if successful. Otherwise,
+/// a JSPError if the root cannot be read.
+pub fn crawl<I: AsRef<Path>>(root: I, graph: &JGraph) -> Result<Vec<CrawlEntry>, JSPError> {
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    crawl_with_threads(root, graph, threads)
+}
+
+/// Crawl the directory tree rooted at `root`, validating each directory using
+/// up to `threads` threads.
+///
+/// # Parameters
+///
+/// * `root` - The absolute path to the directory to start crawling from
+/// * `graph` - Reference to the JGraph
+/// * `threads` - The maximum number of threads to use.
+///
+/// # Returns
+/// Ok wrapping a vector of CrawlEntry, sorted by path, if successful. Otherwise,
+/// a JSPError if the root cannot be read.
+pub fn crawl_with_threads<I: AsRef<Path>>(root: I, graph: &JGraph, threads: usize)
+    -> Result<Vec<CrawlEntry>, JSPError>
+{
+    let root = root.as_ref();
+    if !root.is_dir() {
+        return Err(JSPError::NonExtantPathError(root.to_path_buf()));
+    }
+    let threads = threads.max(1);
+    let mut results = Vec::new();
+    let mut level = vec![root.to_path_buf()];
+
+    while !level.is_empty() {
+        log::debug!("crawl_with_threads(...) validating {} directories", level.len());
+        let chunk_size = (level.len() + threads - 1) / threads;
+        let (entries, next) = thread::scope(|scope| {
+            let handles = level.chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || crawl_chunk(chunk, graph)))
+                .collect::<Vec<_>>();
+
+            let mut entries = Vec::new();
+            let mut next = Vec::new();
+            for handle in handles {
+                let (mut e, mut n) = handle.join().expect("crawl thread panicked");
+                entries.append(&mut e);
+                next.append(&mut n);
+            }
+            (entries, next)
+        });
+        results.extend(entries);
+        level = next;
+    }
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(results)
+}
+
+// Validate each directory in the chunk, returning the entries and the
+// subdirectories which should be crawled next.
+fn crawl_chunk(chunk: &[PathBuf], graph: &JGraph) -> (Vec<CrawlEntry>, Vec<PathBuf>) {
+    let mut entries = Vec::with_capacity(chunk.len());
+    let mut next = Vec::new();
+
+    for dir in chunk {
+        match validate_path(dir, graph) {
+            Ok(nodepath) => {
+                entries.push(CrawlEntry{ path: dir.clone(), failure: None });
+                // only descend if the entire path is tracked by the template, and the
+                // leaf has children
+                let tracked = nodepath.len() == dir.components().count();
+                let has_children = nodepath.nindex().map(|idx| graph.neighbors(idx).next().is_some()).unwrap_or(false);
+                if tracked && has_children {
+                    next.append(&mut subdirectories(dir));
+                }
+            }
+            Err(e) => entries.push(CrawlEntry{ path: dir.clone(), failure: Some(e) }),
+        }
+    }
+    (entries, next)
+}
+
+// Retrieve the subdirectories of dir, skipping symlinks.
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir.filter_map(|entry| entry.ok())
+                                .filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+                                .map(|entry| entry.path())
+                                .collect(),
+        Err(e) => {
+            log::warn!("subdirectories(...) unable to read {:?}: {}", dir, e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jspnode, testutil::{graph_to, TempDir}, EntryType, Node, NodePath, NodeType, PathContext, Regexp, ValidPath};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn graph_and_results_are_send_and_sync() {
+        assert_send_sync::<JGraph>();
+        assert_send_sync::<NodePath<'static>>();
+        assert_send_sync::<ValidPath<'static>>();
+        assert_send_sync::<PathContext>();
+        assert_send_sync::<CrawlEntry>();
+    }
+
+    #[test]
+    fn can_crawl_tree() {
+        let tmp = TempDir::new("crawl_test");
+        tmp.mkdirs(&["DEV01/user/work.jgerber/anything", "DEV01/bad", "lower"]);
+        let root = tmp.path().to_path_buf();
+
+        let (mut graph, parent) = graph_to(&root);
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[0-9]*$"));
+        let user = graph.add_node(jspnode!("user"));
+        let work = graph.add_node(jspnode!("work", r"^work\.(?P<work>[a-z]+)$"));
+        graph.extend_with_edges(&[(parent, show), (show, user), (user, work)]);
+        crate::build_dispatch_index(&mut graph);
+
+        let results = crawl_with_threads(&root, &graph, 4).unwrap();
+
+        let invalid = results.iter().filter(|e| !e.is_valid()).map(|e| e.path.clone()).collect::<Vec<PathBuf>>();
+        assert_eq!(invalid, vec![root.join("DEV01/bad"), root.join("lower")]);
+        let valid = results.iter().filter(|e| e.is_valid()).count();
+        // root, DEV01, user, work.jgerber. anything is beyond the template
+        assert_eq!(valid, 4);
+    }
+}
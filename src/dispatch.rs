@@ -0,0 +1,165 @@
+//! Dispatch provides a per node index of the node's children, allowing the
+//! graph walkers to select the children matching a path component without
+//! testing each child in turn. Simple children are looked up by name, and
+//! RegEx children are matched in a single pass using a RegexSet.
+//!
+//! The index is built once the graph has been loaded, via
+//! `graph::build_dispatch_index`. A node whose index has not been built
+//! reports `is_indexed() == false`, and callers fall back to iterating over
+//! the node's neighbors.
+use crate::{JGraph, NIndex, NodeType};
+use ext_regex::RegexSet;
+use std::{collections::HashMap, fmt};
+
+/// Index of a node's children. Each child is stored along with its position
+/// amongst the node's neighbors, so that results are returned in the same
+/// order as `graph.neighbors(parent)`.
+#[derive(Clone, Default)]
+pub struct Dispatch {
+    indexed: bool,
+    child_count: usize,
+    simple: HashMap<String, Vec<(usize, NIndex)>>,
+    regex_set: Option<RegexSet>,
+    regex: Vec<(usize, NIndex)>,
+    regex_by_name: HashMap<String, Vec<(usize, NIndex)>>,
+    untracked: Vec<(usize, NIndex)>,
+}
+
+// The dispatch index is derived from the graph. It plays no part in
+// determining whether two nodes are equal.
+impl PartialEq for Dispatch {
+    fn eq(&self, _other: &Dispatch) -> bool {
+        true
+    }
+}
+
+impl Eq for Dispatch {}
+
+impl fmt::Debug for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Dispatch{{indexed: {}, children: {}}}", self.indexed, self.child_count)
+    }
+}
+
+impl Dispatch {
+    /// New up a Dispatch for the children of `parent`.
+    ///
+    /// # Parameters
+    ///
+    /// * `parent` - The NIndex of the node whose children we are indexing
+    /// * `graph` - Reference to the JGraph
+    ///
+    /// # Returns
+    /// An indexed Dispatch instance. If the RegexSet fails to compile, an unindexed
+    /// Dispatch is returned, and lookups fall back to iterating over the neighbors.
+    pub fn new(parent: NIndex, graph: &JGraph) -> Self {
+        let mut dispatch = Dispatch::default();
+        let mut patterns = Vec::new();
+
+        for (pos, idx) in graph.neighbors(parent).enumerate() {
+            dispatch.child_count += 1;
+            match graph[idx].identity() {
+                NodeType::Simple(name) => {
+                    dispatch.simple.entry(name.clone()).or_insert_with(Vec::new).push((pos, idx));
+                }
+                NodeType::RegEx{name, pattern, ..} => {
+                    patterns.push(pattern.as_str().to_string());
+                    dispatch.regex.push((pos, idx));
+                    dispatch.regex_by_name.entry(name.clone()).or_insert_with(Vec::new).push((pos, idx));
+                }
+                NodeType::Untracked => dispatch.untracked.push((pos, idx)),
+                NodeType::Root => (),
+            }
+        }
+
+        if !patterns.is_empty() {
+            match RegexSet::new(&patterns) {
+                Ok(set) => dispatch.regex_set = Some(set),
+                Err(e) => {
+                    log::warn!("Dispatch::new(...) unable to build RegexSet for {:?}: {}", parent, e);
+                    return Dispatch::default();
+                }
+            }
+        }
+        dispatch.indexed = true;
+        dispatch
+    }
+
+    /// Has the index been built?
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// The number of children of the node
+    pub fn child_count(&self) -> usize {
+        self.child_count
+    }
+
+    /// Retrieve the children which match the supplied path component, in
+    /// neighbor order. This is equivalent to testing `graph[child] == entry`
+    /// for each child.
+    pub fn matching(&self, entry: &str, graph: &JGraph) -> Vec<NIndex> {
+        let mut matches = Vec::new();
+        if let Some(simple) = self.simple.get(entry) {
+            matches.extend_from_slice(simple);
+        }
+        if let Some(ref set) = self.regex_set {
+            for i in set.matches(entry).iter() {
+                let (pos, idx) = self.regex[i];
+                if let NodeType::RegEx{exclude: Some(exclude), ..} = graph[idx].identity() {
+                    if exclude.is_match(entry) { continue; }
+                }
+                matches.push((pos, idx));
+            }
+        }
+        matches.extend_from_slice(&self.untracked);
+        Self::ordered(matches)
+    }
+
+    /// Retrieve the children which are candidates when searching for the RegEx node
+    /// named `name`, in neighbor order. Simple nodes are always candidates, as they
+    /// are traversed automatically by `find`.
+    pub fn candidates_named(&self, name: &str) -> Vec<NIndex> {
+        let mut matches = self.simple.values().flatten().cloned().collect::<Vec<(usize, NIndex)>>();
+        if let Some(regex) = self.regex_by_name.get(name) {
+            matches.extend_from_slice(regex);
+        }
+        matches.extend_from_slice(&self.untracked);
+        Self::ordered(matches)
+    }
+
+    // sort the matches by position and strip the positions
+    fn ordered(mut matches: Vec<(usize, NIndex)>) -> Vec<NIndex> {
+        matches.sort_by_key(|(pos, _)| *pos);
+        matches.into_iter().map(|(_, idx)| idx).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph_unindexed;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn matching_agrees_with_linear_scan() {
+        let graph = build_graph_unindexed();
+        let entries = ["dd", "shows", "DEV01", "RD", "9999", "SHARED", "MODEL", "char", "user", "work.jgerber", "etc", "FOO"];
+        for parent in graph.node_indices() {
+            let dispatch = Dispatch::new(parent, &graph);
+            assert!(dispatch.is_indexed());
+            for entry in entries.iter() {
+                let linear = graph.neighbors(parent)
+                                  .filter(|n| graph[*n] == *OsStr::new(entry))
+                                  .collect::<Vec<NIndex>>();
+                assert_eq!(dispatch.matching(entry, &graph), linear, "parent {:?} entry {}", parent, entry);
+            }
+        }
+    }
+
+    #[test]
+    fn unindexed_by_default() {
+        let dispatch = Dispatch::default();
+        assert!(!dispatch.is_indexed());
+    }
+}
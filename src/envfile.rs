@@ -0,0 +1,188 @@
+//! Env files hold settings (eg OCIO config, frame rate, resolution) which are
+//! exported on navigating to a location. A template points to an env file,
+//! relative to the node's directory, with the `envfile` metadata key:
+//!
+//! ```text
+//! show = $show [ varname: DD_SHOW, envfile: etc/show.env ]
+//! ```
+//!
+//! Env files are made up of `NAME=value` lines, optionally preceded by
+//! `export`. Blank lines and lines starting with `#` are ignored. Values may be
+//! bare words, which end at the first whitespace, single quoted, in which case
+//! they are taken literally, or double quoted, in which case `\"`, `\\`, `\$`
+//! and `\n` are unescaped. Values are not otherwise expanded.
+//!
+//! Env files may not set the variables which jsp uses to track its own state
+//! (`JSP_*`), nor those which the shell and the processes run from it depend
+//! upon (eg `PATH`), as these would be cleared on leaving the level. Nor may
+//! they set the template's varnames, which `jsp go` skips with a warning.
+use crate::{shell, JSPError};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Env files may not set variables starting with this prefix, which is reserved
+/// for jsp's own state (eg `JSP_VARS`, `JSP_ALIAS_NAMES`, `JSP_NAV_<NAME>`)
+pub const RESERVED_PREFIX: &str = "JSP_";
+
+/// Variables which env files may not set, as the shell and the processes run
+/// from it depend upon them
+pub const PROTECTED_VARS: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "PWD", "OLDPWD", "TERM", "TMPDIR", "IFS",
+    "LD_LIBRARY_PATH", "LD_PRELOAD", "DYLD_LIBRARY_PATH", "DYLD_INSERT_LIBRARIES",
+];
+
+/// Test whether env files may set the variable `name`
+pub fn is_settable(name: &str) -> bool {
+    !name.starts_with(RESERVED_PREFIX) && !PROTECTED_VARS.contains(&name)
+}
+
+/// The variables declared by an env file, in the order declared
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EnvFile {
+    file: PathBuf,
+    vars: Vec<(String, String)>,
+}
+
+impl EnvFile {
+    /// Read and parse the env file at `file`
+    pub fn from_file<I: Into<PathBuf>>(file: I) -> Result<Self, JSPError> {
+        let file = file.into();
+        let contents = fs::read_to_string(&file)?;
+        Self::parse(file, &contents)
+    }
+
+    /// Parse the `contents` of the env file at `file`
+    ///
+    /// # Returns
+    /// Ok wrapping the EnvFile if every line is valid. Otherwise, a
+    /// JSPError::EnvFileError identifying the first invalid line.
+    pub fn parse<I: Into<PathBuf>>(file: I, contents: &str) -> Result<Self, JSPError> {
+        let file = file.into();
+        let mut vars = Vec::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let error = |msg: &str| JSPError::EnvFileError{ file: file.clone(), line: idx + 1, msg: msg.to_string() };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+            let eq = line.find('=').ok_or_else(|| error("expected NAME=value"))?;
+            let name = line[..eq].trim();
+            if !shell::is_var_name(name) {
+                return Err(error(&format!("invalid variable name '{}'", name)));
+            }
+            if !is_settable(name) {
+                return Err(error(&format!("{} may not be set by an env file", name)));
+            }
+            let value = parse_value(line[eq+1..].trim()).map_err(|msg| error(&msg))?;
+            vars.push((name.to_string(), value));
+        }
+        Ok(Self{ file, vars })
+    }
+
+    /// The path to the env file
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// The variables declared by the env file, as pairs of name and value
+    pub fn vars(&self) -> &[(String, String)] {
+        &self.vars
+    }
+}
+
+// parse the value of a NAME=value line, which may be followed by a comment
+fn parse_value(input: &str) -> Result<String, String> {
+    let mut chars = input.chars();
+    let (value, rest) = match chars.next() {
+        None => return Ok(String::new()),
+        Some('\'') => {
+            let end = input[1..].find('\'').ok_or_else(|| "unterminated single quote".to_string())?;
+            (input[1..end+1].to_string(), &input[end+2..])
+        }
+        Some('"') => {
+            let mut value = String::new();
+            let mut consumed = None;
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => {
+                        consumed = Some(input.len() - chars.as_str().len());
+                        break;
+                    }
+                    '\\' => match chars.next() {
+                        Some('n') => value.push('\n'),
+                        Some(c) if "\"\\$".contains(c) => value.push(c),
+                        Some(c) => { value.push('\\'); value.push(c); }
+                        None => return Err("unterminated double quote".to_string()),
+                    },
+                    c => value.push(c),
+                }
+            }
+            let consumed = consumed.ok_or_else(|| "unterminated double quote".to_string())?;
+            (value, &input[consumed..])
+        }
+        Some(_) => {
+            let end = input.find(char::is_whitespace).unwrap_or(input.len());
+            (input[..end].to_string(), &input[end..])
+        }
+    };
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(value)
+    } else {
+        Err(format!("unexpected '{}' following value", rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_env_file() {
+        let contents = r#"
+# show settings
+OCIO=/dd/shows/DEV01/etc/config.ocio
+export FPS=24   # frames per second
+RESOLUTION = "2048 1080"
+NOTE='literal $HOME'
+ESCAPED="a \"b\" \$c"
+EMPTY=
+"#;
+        let envfile = EnvFile::parse("show.env", contents).unwrap();
+        assert_eq!(envfile.vars(), &[
+            ("OCIO".to_string(), "/dd/shows/DEV01/etc/config.ocio".to_string()),
+            ("FPS".to_string(), "24".to_string()),
+            ("RESOLUTION".to_string(), "2048 1080".to_string()),
+            ("NOTE".to_string(), "literal $HOME".to_string()),
+            ("ESCAPED".to_string(), "a \"b\" $c".to_string()),
+            ("EMPTY".to_string(), "".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn invalid_lines_are_err() {
+        let err = EnvFile::parse("show.env", "FPS=24\nnot a setting\n").err();
+        assert_eq!(err, Some(JSPError::EnvFileError{
+            file: PathBuf::from("show.env"), line: 2, msg: "expected NAME=value".to_string()
+        }));
+        assert!(EnvFile::parse("show.env", "1FPS=24").is_err());
+        assert!(EnvFile::parse("show.env", "RES=2048 1080").is_err());
+        assert!(EnvFile::parse("show.env", "RES=\"2048").is_err());
+        assert!(EnvFile::parse("show.env", "RES='2048").is_err());
+    }
+
+    #[test]
+    fn protected_and_reserved_vars_are_err() {
+        let err = EnvFile::parse("show.env", "FPS=24\nexport PATH=/show/bin\n").err();
+        assert_eq!(err, Some(JSPError::EnvFileError{
+            file: PathBuf::from("show.env"), line: 2, msg: "PATH may not be set by an env file".to_string()
+        }));
+        for line in &["LD_LIBRARY_PATH=/show/lib", "JSP_VARS=FPS", "JSP_ALIAS_NAMES=cs", "JSP_NAV_CS=/tmp"] {
+            assert!(EnvFile::parse("show.env", line).is_err(), "{}", line);
+        }
+        assert!(EnvFile::parse("show.env", "SHOW_PATH=/show/bin").is_ok());
+    }
+}
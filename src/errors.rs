@@ -1,9 +1,8 @@
-use crate::{NIndex, NodeType, jspt};
+use crate::{constants, NIndex, NodeType, jspt};
 use failure::Fail;
 use nix;
 use std::{ffi::OsString, io, num, path::PathBuf };
 use ext_regex;
-use levelspecter;
 
 /// Error enum implementing Fail trait
 #[derive(Debug, Fail, PartialEq, Clone)]
@@ -73,8 +72,8 @@ pub enum JSPError {
 
     //#[fail(display = "{}", _0)]
     //IoError(#[cause] io::Error),
-    #[fail(display = "io::Error {}", _0)]
-    IoError(String),
+    #[fail(display = "io::Error {}", msg)]
+    IoError{ kind: io::ErrorKind, msg: String },
 
     #[fail(display = "{}", _0)]
     ParseIntError(#[cause] num::ParseIntError),
@@ -85,9 +84,6 @@ pub enum JSPError {
     #[fail(display = "{}", _0)]
     RegexError(#[cause] ext_regex::Error),
     
-    #[fail(display = "{}", _0)]
-    LevelSpecError(#[cause] levelspecter::LevelSpecterError),
-
     #[fail(display = "Empty argument list")]
     EmptyArgumentListError,
 
@@ -102,6 +98,56 @@ pub enum JSPError {
 
     #[fail(display = "JSPError '{}'", _0)]
     GeneralError(String),
+
+    #[fail(display = "Unable to format path for '{}'. Missing values: {:?}", target, missing)]
+    MissingFormatValues{ target: String, missing: Vec<String> },
+
+    #[fail(display = "Unable to format path: '{}'", _0)]
+    FormatFailure(String),
+
+    #[fail(display = "Ambiguous path for '{}'. Candidates: {:?}", target, candidates)]
+    AmbiguousFormat{ target: String, candidates: Vec<PathBuf> },
+
+    #[fail(display = "Unknown output format: '{}'", _0)]
+    UnknownFormat(String),
+
+    #[fail(display = "Invalid bookmark: '{}'", _0)]
+    BookmarkError(String),
+
+    #[fail(display = "Ambiguous levelspec '{}'. Candidates: {:?}", input, candidates)]
+    AmbiguousLevelspec{ input: String, candidates: Vec<String> },
+
+    #[fail(display = "Invalid env file {:?}, line {}: {}", file, line, msg)]
+    EnvFileError{ file: PathBuf, line: usize, msg: String },
+}
+
+impl JSPError {
+    /// The process exit code which reports the error. Requests which resolve to 
+    /// nothing exit with `EXIT_NOT_FOUND`, malformed or invalid requests with 
+    /// `EXIT_INVALID`, and anything else with `EXIT_INTERNAL_ERROR`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JSPError::FindFailure(_)
+            | JSPError::NonExtantPathError(_)
+            | JSPError::IoError{kind: io::ErrorKind::NotFound, ..} => constants::EXIT_NOT_FOUND,
+
+            JSPError::ValidationFailure{..}
+            | JSPError::ValidationFailureFor{..}
+            | JSPError::ValidationFailureAt{..}
+            | JSPError::SearchTermError(_)
+            | JSPError::VarError(_)
+            | JSPError::EmptyArgumentListError
+            | JSPError::UnknownShell(_)
+            | JSPError::UnknownFormat(_)
+            | JSPError::BookmarkError(_)
+            | JSPError::MissingFormatValues{..}
+            | JSPError::AmbiguousFormat{..}
+            | JSPError::AmbiguousLevelspec{..}
+            | JSPError::EnvFileError{..} => constants::EXIT_INVALID,
+
+            _ => constants::EXIT_INTERNAL_ERROR,
+        }
+    }
 }
 
 impl From<std::env::VarError> for JSPError {
@@ -118,7 +164,7 @@ impl From<jspt::JSPTemplateError> for JSPError {
 
 impl From<io::Error> for JSPError {
     fn from(error: io::Error) -> Self {
-        JSPError::IoError(error.to_string())
+        JSPError::IoError{ kind: error.kind(), msg: error.to_string() }
     }
 }
 
@@ -146,15 +192,22 @@ impl From<std::boxed::Box<dyn std::error::Error>> for JSPError {
     }
 }
 
-// impl From<levelspec::LSpecError> for JSPError {
-//     fn from(error: levelspec::LSpecError) -> Self {
-//         JSPError::LevelSpecError(error)
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn exit_codes_distinguish_failures() {
+        assert_eq!(JSPError::FindFailure("foo".to_string()).exit_code(), constants::EXIT_NOT_FOUND);
+        assert_eq!(JSPError::EmptyArgumentListError.exit_code(), constants::EXIT_INVALID);
+        assert_eq!(JSPError::JGraphError("foo".to_string()).exit_code(), constants::EXIT_INTERNAL_ERROR);
+    }
 
-impl From<levelspecter::LevelSpecterError> for JSPError {
-    fn from(error: levelspecter::LevelSpecterError) -> Self {
-        JSPError::LevelSpecError(error)
+    #[test]
+    fn only_missing_files_are_not_found() {
+        let missing = JSPError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(missing.exit_code(), constants::EXIT_NOT_FOUND);
+        let denied = JSPError::from(io::Error::new(io::ErrorKind::PermissionDenied, "permission denied"));
+        assert_eq!(denied.exit_code(), constants::EXIT_INTERNAL_ERROR);
     }
-}
\ No newline at end of file
+}
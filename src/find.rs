@@ -1,5 +1,5 @@
 use crate::{ JGraph, JSPError, NodePath, NodeType, NIndex, Search, SearchTerm, MetadataTerm};
-use std::{ cell::RefCell, rc::Rc, collections::VecDeque, path::PathBuf };
+use std::{ collections::VecDeque, path::PathBuf };
 use log;
 use petgraph::{visit::IntoNodeReferences, Direction::Outgoing};
 use ext_regex::Regex;
@@ -58,6 +58,38 @@ pub fn find_path<'a>(search: &Search, graph: &'a JGraph)
     let keys = search.keys_owned();
     log::debug!("find_path(...) Keys: {:?}", &keys);
     let nodepath = find(keys, graph)?;
+    let path = path_from_search(search, &nodepath)?;
+    log::info!("find-path(...) returning {:?} {:?}", path, &nodepath.nodes);
+    Ok((path, nodepath))
+}
+
+/// Given a Search reference, the NIndex of a starting node, and a JGraph reference, 
+/// find the PathBuf, relative to the starting node, represented by the search, 
+/// or return an error if unsuccessful.
+/// 
+/// # Parameters
+/// 
+/// * `search` - A reference to the `Search` struct. 
+/// * `start` - The NIndex of the node to start the search from
+/// * `graph` - A reference to a JGraph
+/// 
+/// # Returns
+/// 
+/// if successful, a tuple of the relative `PathBuf`, and a `NodePath` storing the 
+/// corresponding `Node`s for the path, not including the starting node. Otherwise a 
+/// relevant JSPError if not successful. 
+pub fn find_path_from<'a>(search: &Search, start: NIndex, graph: &'a JGraph) 
+    -> Result<(PathBuf, NodePath<'a>), JSPError> 
+{
+    log::info!("find_path_from(search: {:?}, start: {:?}, graph)", search, start);
+    let nodepath = find_from(search.keys_owned(), start, graph)?;
+    let path = path_from_search(search, &nodepath)?;
+    Ok((path, nodepath))
+}
+
+// Build a PathBuf from the nodes in the nodepath, taking the values for
+// named nodes, in order, from the search
+fn path_from_search(search: &Search, nodepath: &NodePath) -> Result<PathBuf, JSPError> {
     let mut values = search.values_owned();
     let mut path = PathBuf::new();
 
@@ -129,8 +161,7 @@ pub fn find_path<'a>(search: &Search, graph: &'a JGraph)
         }
     }
     
-    log::info!("find-path(...) returning {:?} {:?}", path, &nodepath.nodes);
-    Ok((path, nodepath))
+    Ok(path)
 }
 
 fn has_named_captures(input: &str) -> bool {
@@ -193,57 +224,70 @@ fn replace_capture_group(regstr: &str, key: &str, replacement: &str) -> Option<S
 /// ```
 pub fn find<'a>(criteria: VecDeque<String>, graph: &'a JGraph) -> Result<NodePath<'a>, JSPError> {
     let idx = graph.node_references().next().unwrap().0;
-    let np = vec![idx];
-    let vec_nodes   = Rc::new(RefCell::new(np));
-    let criteria_rc = Rc::new(RefCell::new(criteria));
-
-    match find_recurse(criteria_rc.clone(), vec_nodes, graph) {
-        FindValue::Success(npath) => {
-            let mut npath = Rc::try_unwrap(npath)
-                          .unwrap()
-                          .into_inner();
-            let mut nodepath = NodePath::new(&graph);
-            nodepath.append_unchecked(&mut npath);
-            Ok(nodepath)
-        },
-        FindValue::Failure(_) => Err(JSPError::FindFailure(format!("Unable to match one or more criteria: {:?}", criteria_rc.borrow()))),
+    let mut nodepath = NodePath::new(&graph);
+    nodepath.append_unchecked(&mut find_indices(criteria, idx, graph)?);
+    Ok(nodepath)
+}
+
+/// Find a NodePath, relative to the supplied starting node, given a vector 
+/// of criteria Strings and a JGraph reference
+///
+/// # Parameters
+/// * `criteria` - VecDeque of Strings representing a path through the graph of
+///                RegEx Nodes, as with `find`. 
+/// * `start` - The NIndex of the node to start the search from
+/// * `graph` - JGraph reference 
+/// # Returns
+///    A NodePath, not including the starting node, if successful. Otherwise, a JSPError
+pub fn find_from<'a>(criteria: VecDeque<String>, start: NIndex, graph: &'a JGraph) -> Result<NodePath<'a>, JSPError> {
+    let mut indices = find_indices(criteria, start, graph)?;
+    indices.remove(0);
+    let mut nodepath = NodePath::new(&graph);
+    nodepath.append_unchecked(&mut indices);
+    Ok(nodepath)
+}
+
+// Find the indices matching the criteria, starting with, and including, start
+fn find_indices(mut criteria: VecDeque<String>, start: NIndex, graph: &JGraph) -> Result<Vec<NIndex>, JSPError> {
+    let mut nodepath = vec![start];
+
+    match find_recurse(&mut criteria, &mut nodepath, graph) {
+        FindValue::Success => Ok(nodepath),
+        FindValue::Failure => Err(JSPError::FindFailure(format!("Unable to match one or more criteria: {:?}", criteria))),
     }
 }
 
-// Internal recursive function
-fn find_recurse<'a>(
-    criteria: Rc<RefCell<VecDeque<String>>> ,
-    nodepath: Rc<RefCell<Vec<NIndex>>>,
-    graph: &'a JGraph,
+// Internal recursive function. On success, nodepath holds the indices of the 
+// found nodes. On failure, criteria and nodepath are restored to their state 
+// prior to the call.
+fn find_recurse(
+    criteria: &mut VecDeque<String>,
+    nodepath: &mut Vec<NIndex>,
+    graph: &JGraph,
 ) -> FindValue {
     log::info!("");
-    log::info!("find_recurse({:?}, {:?})", criteria, nodepath.borrow());
-
-    // explicit lifetime bounds for criteria_borrow. Should test to see
-    // if NLL works here. ie is this needed?
-    {
-        let criteria_borrow = criteria.borrow();
-        if criteria_borrow.len() == 0 {
-            return FindValue::Success(nodepath);
-        }
-    }
+    log::info!("find_recurse({:?}, {:?})", criteria, nodepath);
 
-    let front_criteria_elem;
-    {
-        let mut criteria_borrow = criteria.borrow_mut();
-        front_criteria_elem = criteria_borrow.pop_front();
-        log::info!("find_recurse(...) front_criteria_elem {:?}", front_criteria_elem);
+    if criteria.is_empty() {
+        return FindValue::Success;
     }
 
+    let front_criteria_elem = criteria.pop_front();
+    log::info!("find_recurse(...) front_criteria_elem {:?}", front_criteria_elem);
+
     match front_criteria_elem {
         Some(candidate_node_name) => {
-            let last_node;
-            {
-                let np = nodepath.borrow();
-                last_node = np[np.len()-1];
-            }
+            let last_node = nodepath[nodepath.len()-1];
             log::debug!("find_recurse(...) last node: {:?}. Iterating through last_node's children... to match candidate {}", last_node, candidate_node_name);
-            for nindex in graph.neighbors(last_node) {
+            // use the dispatch index, if it has been built, to skip RegEx nodes 
+            // whose name does not match the candidate
+            let dispatch = graph[last_node].dispatch();
+            let children = if dispatch.is_indexed() {
+                dispatch.candidates_named(&candidate_node_name)
+            } else {
+                graph.neighbors(last_node).collect::<Vec<NIndex>>()
+            };
+            for nindex in children {
                 let node = &graph[nindex];
                 log::debug!("find_recurse(...) for nindex in neighbors()... node: {:?}, nindex: {:?}", node, nindex);
                 match node.identity() {
@@ -251,15 +295,13 @@ fn find_recurse<'a>(
                         log::debug!("NodeType::RegEx - find_recurse(...)");
                         if name == &candidate_node_name {
                             log::debug!("NodeType::RegEx - find_recurse(...) {} == {}", name, &candidate_node_name);
-                             {
-                                 nodepath.borrow_mut().push(nindex);
-                             }
-                            let r = find_recurse(criteria.clone(), nodepath.clone(), graph);
+                            nodepath.push(nindex);
+                            let r = find_recurse(criteria, nodepath, graph);
                             if r.is_success() {
                                 log::debug!("NodeType::Regex - find_recurse(...) successful");
-                                return FindValue::Success(nodepath);
+                                return FindValue::Success;
                             } else {
-                                let val = nodepath.borrow_mut().pop();
+                                let val = nodepath.pop();
                                 log::debug!("NodeType::Regex - find_recurse unsuccessful. popping {:?} off of nodepath", val);
                             }
                         } else {
@@ -273,49 +315,42 @@ fn find_recurse<'a>(
                         // 2) Add the candidate_node_name to the front of the candidate VecQueue
                         // We do this because we accept the Simple nodetype without a means
                         // test, unlike NodeType::RegEx.
-                        {
-                            log::debug!("NodeType::Simple - pushing criteria: {}", candidate_node_name);
-                            nodepath.borrow_mut().push(nindex);
-                            criteria.borrow_mut().push_front(candidate_node_name.clone());
-                        }
+                        log::debug!("NodeType::Simple - pushing criteria: {}", candidate_node_name);
+                        nodepath.push(nindex);
+                        criteria.push_front(candidate_node_name.clone());
 
-                        let r = find_recurse(criteria.clone(), nodepath.clone(), graph);
+                        let r = find_recurse(criteria, nodepath, graph);
                         if r.is_success() {
                             log::debug!("NodeType::Simple - find_recurse successful");
-                            return FindValue::Success(nodepath);
+                            return FindValue::Success;
                         } else {
-                            //log::debug!("find_recurse unsuccessful");
                             // If we did not find what we were looking for
                             // we pop off the last nodepath item, and we pop off the front
                             // of the criteria vector, both of which we added prior to calling
                             // the recursion
-                            {
-                                nodepath.borrow_mut().pop();
-                                let front = criteria.borrow_mut().pop_front();
-                                log::debug!("Node::simple - find_recerse unsuccessful. poping {:?} from criteria", front);
-                            }
+                            nodepath.pop();
+                            let front = criteria.pop_front();
+                            log::debug!("Node::simple - find_recerse unsuccessful. poping {:?} from criteria", front);
                         }
                     },
                     NodeType::Root =>  {
                         // This should only be added to an empty nodepath
-                        assert_eq!(nodepath.borrow().len(), 0);
-                        return find_recurse(criteria.clone(), nodepath.clone(), graph);
+                        assert_eq!(nodepath.len(), 0);
+                        return find_recurse(criteria, nodepath, graph);
                     },
                     // If we have gone into untracked territory, then we know we have failed
-                    NodeType::Untracked => {return FindValue::Failure(nodepath);},
+                    NodeType::Untracked => {return FindValue::Failure;},
                 }
             }
 
-            {
-                // do i have to push front criteria back?
-                criteria.borrow_mut().push_front(candidate_node_name);
-            }
+            // restore the criteria
+            criteria.push_front(candidate_node_name);
             // made it through all of the children without returning
             // a successful match, so we must be in a failure state.
-            FindValue::Failure(nodepath)
+            FindValue::Failure
         }
         None => {
-            FindValue::Failure(nodepath)
+            FindValue::Failure
         }
     }
 }
@@ -325,15 +360,15 @@ fn find_recurse<'a>(
 // at the outset what would be needed.
 #[derive(Debug, PartialEq, Eq)]
 enum FindValue {
-    Success(Rc<RefCell<Vec<NIndex>>>),
-    Failure(Rc<RefCell<Vec<NIndex>>>)
+    Success,
+    Failure,
 }
 
 impl FindValue {
     /// Return whether or not the instance of FindValue is Success
     fn is_success(&self) -> bool {
         match self {
-            FindValue::Success(_) => true,
+            FindValue::Success => true,
             _ => false,
         }
     }
@@ -450,6 +485,18 @@ mod tests {
         assert_eq!(result.0, PathBuf::from("/dd/shows/DEV01/RD/0001/user/work.jgerber"));
 
     }
+
+    #[test]
+    fn will_find_path_from_node() {
+        let graph = build_graph();
+        let shot = crate::validate_path("/dd/shows/DEV01/RD/0001", &graph).unwrap();
+        let  mut search =  Search::new();
+        search.push_back(SearchTerm::new("work", "jgerber"));
+
+        let (path, nodepath) = find_path_from(&search, shot.nindex().unwrap(), &graph).unwrap();
+        assert_eq!(path, PathBuf::from("user/work.jgerber"));
+        assert_eq!(nodepath.len(), 2);
+    }
 }
 
 #[derive(Debug,PartialEq, Eq, Clone)]
@@ -462,10 +509,10 @@ pub enum FindRelStrategy {
 pub fn find_rel(starting_index: NIndex, criteria: MetadataTerm, graph: &JGraph, strategy: FindRelStrategy) 
     -> Result<Vec<NodePath>, JSPError> 
 {   
-    let current = Rc::new(RefCell::new(Vec::new()));
+    let mut current = Vec::new();
     let results = match strategy {
-        FindRelStrategy::Deepest => find_rel_recurse_deepest(starting_index, criteria, current.clone() , Vec::new(), graph),
-        FindRelStrategy::First => find_rel_recurse_first(starting_index, criteria, current.clone() , Vec::new(), graph)
+        FindRelStrategy::Deepest => find_rel_recurse_deepest(starting_index, criteria, &mut current, Vec::new(), graph),
+        FindRelStrategy::First => find_rel_recurse_first(starting_index, criteria, &mut current, Vec::new(), graph)
     };
     log::trace!("find_rel returning {:?}", results);
     Ok(results)
@@ -477,15 +524,14 @@ fn find_rel_recurse_deepest<'a>(
     parent_idx: NIndex,
     criteria: MetadataTerm,
     // current vec of indices that form a candidate nodepath
-    current: Rc<RefCell<Vec<NIndex>>>,
+    current: &mut Vec<NIndex>,
     // vector of confirmed, foud nodepaths
-    //nodepaths: Rc<RefCell<Vec<NodePath>>>,
     mut nodepaths: Vec<NodePath<'a>>,
     // reference to the JGraph
     graph: &'a JGraph,
 ) -> Vec<NodePath<'a> > {
     //let mut nodepaths = nodepaths;
-    log::trace!("find_rel_recurse called with current:{:?} nodepaths:{:?}", current.borrow(), nodepaths);
+    log::trace!("find_rel_recurse called with current:{:?} nodepaths:{:?}", current, nodepaths);
     let mut cnt = -1;
     let parent = &graph[parent_idx];
     for nindex in graph.neighbors_directed(parent_idx, Outgoing)  {
@@ -497,14 +543,14 @@ fn find_rel_recurse_deepest<'a>(
                 if criteria == *node.metadata()  {
                     log::trace!("SIMPLE loop idx: {} criteria  {:?} matches node: {} index {:?} for parent: {:?}",cnt, criteria, val, nindex, parent);
                    // add idx to current
-                   current.borrow_mut().push(nindex);
+                   current.push(nindex);
                     
-                   find_rel_recurse_deepest(nindex, criteria, current.clone(), nodepaths, graph)
+                   find_rel_recurse_deepest(nindex, criteria, current, nodepaths, graph)
                 } else {
                     log::trace!("Simple val {} does NOT match criterial {:?}. calling update_and_return_nodepaths()", val, criteria);
                     // do we have any captured current indices which need to be 
                     // turned into nodepaths? update_and_return_nodepaths() returns nodepaths
-                    //update_and_return_nodepaths(nodepaths, current.clone(), graph)
+                    //update_and_return_nodepaths(nodepaths, current, graph)
                     nodepaths
                     
                 }
@@ -514,7 +560,7 @@ fn find_rel_recurse_deepest<'a>(
                 if criteria == *node.metadata()  {
                     // cant match this currently
                     log::debug!("matched regex {} with metadata.currently not supported", name);
-                    update_and_return_nodepaths(nodepaths, current.clone(), graph)
+                    update_and_return_nodepaths(nodepaths, current, graph)
                 } else {
                     nodepaths
                 }
@@ -527,12 +573,12 @@ fn find_rel_recurse_deepest<'a>(
             NodeType::Untracked => {
                 // we check to see if current has anything in it. If it does, we need to 
                 // create a new nodepath from the stuff inside
-                //update_and_return_nodepaths(nodepaths, current.clone(), graph)
+                //update_and_return_nodepaths(nodepaths, current, graph)
                 nodepaths
             }
         }
     }
-    update_and_return_nodepaths(nodepaths, current.clone(), graph)
+    update_and_return_nodepaths(nodepaths, current, graph)
 }
 
 
@@ -543,15 +589,14 @@ fn find_rel_recurse_first<'a>(
     parent_idx: NIndex,
     criteria: MetadataTerm,
     // current vec of indices that form a candidate nodepath
-    current: Rc<RefCell<Vec<NIndex>>>,
+    current: &mut Vec<NIndex>,
     // vector of confirmed, foud nodepaths
-    //nodepaths: Rc<RefCell<Vec<NodePath>>>,
     mut nodepaths: Vec<NodePath<'a>>,
     // reference to the JGraph
     graph: &'a JGraph
 ) -> Vec<NodePath<'a> > {
     //let mut nodepaths = nodepaths;
-    log::debug!("find_rel_recurse called with current:{:?} nodepaths:{:?}", current.borrow(), nodepaths);
+    log::debug!("find_rel_recurse called with current:{:?} nodepaths:{:?}", current, nodepaths);
     let mut cnt = -1;
     let parent = &graph[parent_idx];
     for nindex in graph.neighbors_directed(parent_idx, Outgoing)  {
@@ -565,21 +610,21 @@ fn find_rel_recurse_first<'a>(
                     log::debug!("SIMPLE loop idx: {} criteria  {:?} matches node: {} index {:?} for parent: {:?}",cnt, criteria, val, nindex, parent);
                    // add idx to current
                     log::debug!("calling update_amd_return_nodepaths");
-                    current.borrow_mut().push(nindex);  
-                    update_and_return_nodepaths_2(nodepaths, current.clone(), graph)
+                    current.push(nindex);  
+                    update_and_return_nodepaths_2(nodepaths, current, graph)
                  
                 } else {
                     //log::trace!("Simple val {} does NOT match criterial {:?}. calling update_and_return_nodepaths()", val, criteria);
                     // do we have any captured current indices which need to be 
                     // turned into nodepaths? update_and_return_nodepaths() returns nodepaths
-                    //update_and_return_nodepaths(nodepaths, current.clone(), graph)
+                    //update_and_return_nodepaths(nodepaths, current, graph)
                    
-                    log::debug!("Nodetype::Simple ({:?}) != node.metadata (metadata.navalias: {:?}). calling find_rel_recurse", criteria, *node.metadata().navalias());
+                    log::debug!("Nodetype::Simple ({:?}) != node.metadata (metadata.navaliases: {:?}). calling find_rel_recurse", criteria, node.metadata().navaliases());
 
                     // as long this is a simple node, we can dive deeper...
-                    current.borrow_mut().push(nindex);
-                    let nodepathvec = find_rel_recurse_first(nindex, criteria, current.clone(), nodepaths, graph);
-                    current.borrow_mut().pop();
+                    current.push(nindex);
+                    let nodepathvec = find_rel_recurse_first(nindex, criteria, current, nodepaths, graph);
+                    current.pop();
                     nodepathvec
                 }
             }
@@ -588,8 +633,8 @@ fn find_rel_recurse_first<'a>(
                 log::debug!("NodeType::RegEx searching regex node {}", name);
                 if criteria == *node.metadata()  {
                     log::debug!("criteria == node.metadata");
-                    current.borrow_mut().push(nindex);
-                    update_and_return_nodepaths_2(nodepaths, current.clone(), graph)
+                    current.push(nindex);
+                    update_and_return_nodepaths_2(nodepaths, current, graph)
                 } else {
                     log::debug!("Nodetype::regex criteria != node.metadata. returning");
                     nodepaths
@@ -604,7 +649,7 @@ fn find_rel_recurse_first<'a>(
                 // we check to see if current has anything in it. If it does, we need to 
                 // create a new nodepath from the stuff inside
                 log::debug!("Nodetype::untracked  returning nodepaths");
-                //update_and_return_nodepaths(nodepaths, current.clone(), graph)
+                //update_and_return_nodepaths(nodepaths, current, graph)
                 nodepaths
             }
         }
@@ -617,18 +662,12 @@ fn find_rel_recurse_first<'a>(
 // update the nodepaths if the current list of NIndex is not empty. reset the vec, and return the nodepaths
 fn update_and_return_nodepaths<'b>(
     mut nodepaths: Vec<NodePath<'b>>,
-    current: Rc<RefCell<Vec<NIndex>>>, 
+    current: &mut Vec<NIndex>, 
     graph: &'b JGraph
 ) -> Vec<NodePath<'b>> 
 {
-     if current.borrow().len() > 0 {
-        let my_current = Rc::new(RefCell::new(Vec::new()));
-        {
-            std::mem::swap(&mut *my_current.borrow_mut(), &mut *current.borrow_mut());
-        }
-        let mut npath = Rc::try_unwrap(my_current)
-                .unwrap()
-                .into_inner();
+    if !current.is_empty() {
+        let mut npath = std::mem::replace(current, Vec::new());
         let mut np = NodePath::new(&graph);
         np.append_unchecked(&mut npath);
         log::debug!("update_and_return_nodepath() updating nodepaths with npath {:?} {}", &np, np.path_string().as_str());
@@ -641,25 +680,19 @@ fn update_and_return_nodepaths<'b>(
 // Pop off the last item in current if current has indices.
 fn update_and_return_nodepaths_2<'b>(
     mut nodepaths: Vec<NodePath<'b>>,
-    current: Rc<RefCell<Vec<NIndex>>>, 
+    current: &mut Vec<NIndex>, 
     graph: &'b JGraph
 ) -> Vec<NodePath<'b>> 
 {
-    let current_len = current.borrow().len();
+    let current_len = current.len();
     if current_len > 0 {
-        let my_current = Rc::new(RefCell::new(Vec::with_capacity(current_len)));
-        {
-            std::mem::swap(&mut *my_current.borrow_mut(), &mut *current.borrow_mut());
-        }
-        let mut npath = Rc::try_unwrap(my_current)
-                .unwrap()
-                .into_inner();
+        let mut npath = std::mem::replace(current, Vec::with_capacity(current_len));
         
         // we want to make sure that we have 
         let one_less = current_len - 1;
         for (cnt, idx) in npath.iter().enumerate() {
             if cnt < one_less {
-                current.borrow_mut().push(*idx);
+                current.push(*idx);
             }
         }
         let mut np = NodePath::new(&graph);
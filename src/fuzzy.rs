@@ -0,0 +1,164 @@
+//! Fuzzy resolution of partial levelspecs. Each level of a partial levelspec
+//! (eg `dev.r.99`) is matched, case insensitively, against the directories on
+//! disk which satisfy the corresponding template level, and the combination
+//! which matches best is returned. A level matches a directory exactly, as a
+//! prefix, as a substring, or as an abbreviation (the characters of the level
+//! appear in order within the directory name), in order of preference.
+use crate::{
+    complete::{disk_completions, resolve_key, root},
+    JGraph,
+    JSPError,
+    Levels,
+    NIndex,
+};
+use std::{
+    env,
+    path::Path,
+};
+
+/// The maximum number of candidates reported for an ambiguous levelspec
+pub const MAX_CANDIDATES: usize = 10;
+
+/// Score how well `partial` matches `candidate`, ignoring case. Lower is better:
+/// 0 for an exact match, 1 for a prefix, 2 for a substring, and 3 for an
+/// abbreviation. None if `partial` does not match.
+pub fn match_score(partial: &str, candidate: &str) -> Option<u32> {
+    let partial = partial.to_uppercase();
+    let candidate = candidate.to_uppercase();
+    if partial == candidate {
+        Some(0)
+    } else if candidate.starts_with(&partial) {
+        Some(1)
+    } else if candidate.contains(&partial) {
+        Some(2)
+    } else {
+        let mut chars = candidate.chars();
+        if partial.chars().all(|p| chars.any(|c| c == p)) { Some(3) } else { None }
+    }
+}
+
+/// Resolve a partial levelspec to the single levelspec of an existing location.
+/// Empty levels (eg `.r.99`) are taken from the environment variables named by
+/// the levels' varnames.
+///
+/// # Parameters
+///
+/// * `partial` - The partial levelspec
+/// * `graph` - Reference to the JGraph
+///
+/// # Returns
+/// Ok wrapping the levelspec if a single location matches better than any
+/// other. A JSPError::AmbiguousLevelspec, listing the best candidates first,
+/// if several match equally well, and a JSPError::FindFailure if none match.
+pub fn resolve_levelspec(partial: &str, graph: &JGraph) -> Result<String, JSPError> {
+    let levels = Levels::of(graph);
+    let segments = partial.split('.').collect::<Vec<&str>>();
+    if segments.len() > levels.len() {
+        return Err(JSPError::SearchTermError(format!("too many levels in '{}'", partial)));
+    }
+    // pair each segment with the name of its level
+    let segments = segments.iter().zip(levels.iter()).map(|(segment, level)| {
+        let value = match (segment.is_empty(), &level.varname) {
+            (false, _) => segment.to_string(),
+            (true, Some(varname)) => env::var(varname)?,
+            (true, None) => return Err(JSPError::SearchTermError(format!("the {} level of '{}' is empty", level.name, partial))),
+        };
+        Ok((level.name.as_str(), value))
+    }).collect::<Result<Vec<(&str, String)>, JSPError>>()?;
+
+    let (dir, idx) = root(graph)?;
+    let mut candidates = Vec::new();
+    expand(&segments, &dir, idx, &mut Vec::new(), 0, graph, &mut candidates);
+    pick(partial, candidates)
+}
+
+/// Choose the best of the candidate levelspecs, each of which is paired with
+/// its score. The best candidate is returned if it scores strictly better than
+/// the rest. Ties are reported, best first, as a JSPError::AmbiguousLevelspec.
+pub fn pick(partial: &str, mut candidates: Vec<(u32, String)>) -> Result<String, JSPError> {
+    candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.len().cmp(&b.1.len())).then(a.1.cmp(&b.1)));
+    match candidates.len() {
+        0 => Err(JSPError::FindFailure(format!("No location matches '{}'", partial))),
+        1 => Ok(candidates.remove(0).1),
+        _ if candidates[0].0 < candidates[1].0 => Ok(candidates.remove(0).1),
+        _ => Err(JSPError::AmbiguousLevelspec{
+            input: partial.to_string(),
+            candidates: candidates.into_iter().take(MAX_CANDIDATES).map(|(_, c)| c).collect(),
+        }),
+    }
+}
+
+// match the segment at the current depth against the directories satisfying its
+// level beneath dir, recursing for each match. Complete matches are pushed onto 
+// candidates, along with their total score.
+fn expand(
+    segments: &[(&str, String)],
+    dir: &Path,
+    idx: NIndex,
+    path: &mut Vec<String>,
+    score: u32,
+    graph: &JGraph,
+    candidates: &mut Vec<(u32, String)>
+) {
+    let depth = path.len();
+    if depth == segments.len() {
+        candidates.push((score, path.join(".")));
+        return;
+    }
+    let (level, segment) = &segments[depth];
+    let (dir, target) = match resolve_key(level, dir, idx, graph) {
+        Ok(resolved) => resolved,
+        Err(_) => return,
+    };
+    let entries = disk_completions(target, &dir, graph, |name, _| Some(name.to_string()));
+    for entry in entries {
+        if let Some(entry_score) = match_score(segment, &entry.value) {
+            path.push(entry.value.clone());
+            expand(segments, &dir.join(&entry.value), target, path, score + entry_score, graph, candidates);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_score_matches() {
+        assert_eq!(match_score("dev01", "DEV01"), Some(0));
+        assert_eq!(match_score("dev", "DEV01"), Some(1));
+        assert_eq!(match_score("99", "9999"), Some(1));
+        assert_eq!(match_score("v0", "DEV01"), Some(2));
+        assert_eq!(match_score("d1", "DEV01"), Some(3));
+        assert_eq!(match_score("x", "DEV01"), None);
+    }
+
+    #[test]
+    fn picks_best_candidate() {
+        let candidates = vec![(2, "DEV02.RD.0001".to_string()), (1, "DEV01.RD.0001".to_string())];
+        assert_eq!(pick("dev.rd.0001", candidates), Ok("DEV01.RD.0001".to_string()));
+    }
+
+    #[test]
+    fn ties_are_ambiguous() {
+        let candidates = vec![
+            (3, "DEV02.RD.0001".to_string()),
+            (3, "DEV01.RD.0001".to_string()),
+            (5, "DEVL.RD.0001".to_string())
+        ];
+        match pick("dev.rd.1", candidates) {
+            Err(JSPError::AmbiguousLevelspec{candidates, ..}) => assert_eq!(candidates, vec![
+                "DEV01.RD.0001".to_string(),
+                "DEV02.RD.0001".to_string(),
+                "DEVL.RD.0001".to_string()
+            ]),
+            other => panic!("expected ambiguous levelspec, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_candidates_is_not_found() {
+        assert!(pick("foo", Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! GoPlan describes the changes to the caller's environment which navigating
+//! to a location entails - the directory to change to, the environment 
+//! variables to set and unset, and the aliases to set and clear. 
+//!
+//! `jsp go` renders the plan as shell code, via a `ShellEnvManager`, for 
+//! `jspgo` to `eval`. Tools which do not wish to eval shell code may request 
+//! the plan as json instead. `jsp env` renders the plan without changing
+//! directory.
+use crate::{shell, JSPError, ShellEnvManager, SupportedShell};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// The output formats supported by `jsp go`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum GoFormat {
+    /// Shell code, to be `eval`ed
+    #[default]
+    Shell,
+    /// The GoPlan, as json
+    Json,
+}
+
+impl FromStr for GoFormat {
+    type Err = JSPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shell" => Ok(GoFormat::Shell),
+            "json" => Ok(GoFormat::Json),
+            _ => Err(JSPError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// The output formats supported by `jsp env`
+#[derive(Debug, PartialEq, Eq)]
+pub enum EnvFormat {
+    /// Shell code for the supplied shell, to be `eval`ed
+    Shell(SupportedShell),
+    /// `NAME=value` lines, as read by dotenv
+    Dotenv,
+    /// The GoPlan, as json
+    Json,
+}
+
+impl Default for EnvFormat {
+    fn default() -> Self {
+        EnvFormat::Shell(SupportedShell::Bash)
+    }
+}
+
+impl FromStr for EnvFormat {
+    type Err = JSPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dotenv" => Ok(EnvFormat::Dotenv),
+            "json" => Ok(EnvFormat::Json),
+            _ => match SupportedShell::from_str(s) {
+                Ok(myshell) => Ok(EnvFormat::Shell(myshell)),
+                Err(_) => Err(JSPError::UnknownFormat(s.to_string())),
+            }
+        }
+    }
+}
+
+/// What navigating did, given that the requested location may not exist
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GoOutcome {
+    /// The requested location exists, and is the target
+    #[default]
+    Exists,
+    /// The requested location does not exist. The target is its deepest existing ancestor.
+    Ancestor{ requested: PathBuf },
+    /// The requested location did not exist, and was created
+    Created,
+}
+
+/// The changes to the environment required to navigate to `target`. 
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct GoPlan {
+    /// The directory to change to
+    pub target: PathBuf,
+    /// Environment variables to set, by name
+    pub set_vars: BTreeMap<String, String>,
+    /// Environment variables to unset. These are unset before `set_vars` are set.
+    pub unset_vars: Vec<String>,
+    /// Aliases to set, by name, to the directory each changes to
+    pub set_aliases: BTreeMap<String, PathBuf>,
+    /// Aliases to clear. These are cleared before `set_aliases` are set.
+    pub clear_aliases: Vec<String>,
+    /// Whether the target is the requested location, its nearest existing 
+    /// ancestor, or a newly created location
+    #[serde(default)]
+    pub outcome: GoOutcome,
+}
+
+impl GoPlan {
+    /// New up an empty GoPlan for the supplied target directory
+    pub fn new<I: Into<PathBuf>>(target: I) -> Self {
+        Self {
+            target: target.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Render the plan as code for the supplied shell, which, when eval'ed, 
+    /// carries the plan out.
+    pub fn render(&self, shell: &dyn ShellEnvManager) -> String {
+        let mut script = self.render_env(shell);
+        let target = self.target.to_string_lossy();
+        script.push_str(&format!("cd {};\n", shell.quote(&target)));
+        let message = match &self.outcome {
+            GoOutcome::Exists => format!("Changed Directory To: {}", target),
+            GoOutcome::Ancestor{requested} => 
+                format!("Changed Directory To: {} ({} does not exist)", target, requested.display()),
+            GoOutcome::Created => format!("Changed Directory To: {} (created)", target),
+        };
+        script.push_str(&format!("echo {}\n\n", shell.quote(&message)));
+        script
+    }
+
+    /// Render the changes to the environment variables and aliases as code for the
+    /// supplied shell, without changing directory.
+    pub fn render_env(&self, shell: &dyn ShellEnvManager) -> String {
+        let mut script = String::new();
+        for varname in &self.unset_vars {
+            script.push_str(&shell.clear_env_var(varname));
+        }
+        for (varname, value) in &self.set_vars {
+            script.push_str(&shell.set_env_var(varname, value));
+        }
+        for name in &self.clear_aliases {
+            script.push_str(&shell.unset_alias(name));
+        }
+        for (name, value) in &self.set_aliases {
+            script.push_str(&shell.set_alias(name, value));
+        }
+        script
+    }
+
+    /// Render the environment variables to set as dotenv `NAME=value` lines. Dotenv
+    /// has no means of unsetting variables or setting aliases, so these are omitted.
+    pub fn to_dotenv(&self) -> String {
+        let mut env = String::new();
+        for (varname, value) in &self.set_vars {
+            if !shell::is_var_name(varname) { continue; }
+            if shell::is_bare_word(value) {
+                env.push_str(&format!("{}={}\n", varname, value));
+            } else {
+                let escaped = value.replace('\\', r"\\")
+                                   .replace('"', r#"\""#)
+                                   .replace('$', r"\$")
+                                   .replace('\n', r"\n");
+                env.push_str(&format!("{}=\"{}\"\n", varname, escaped));
+            }
+        }
+        env
+    }
+
+    /// Render the plan in the supplied `jsp env` format
+    pub fn render_as(&self, format: &EnvFormat) -> Result<String, JSPError> {
+        match format {
+            EnvFormat::Shell(myshell) => Ok(self.render_env(&*myshell.get())),
+            EnvFormat::Dotenv => Ok(self.to_dotenv()),
+            EnvFormat::Json => self.to_json().map(|json| format!("{}\n", json)),
+        }
+    }
+
+    /// Render the plan as json
+    pub fn to_json(&self) -> Result<String, JSPError> {
+        serde_json::to_string(self).map_err(|e| JSPError::GeneralError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bash, tcsh};
+
+    fn build_plan() -> GoPlan {
+        let mut plan = GoPlan::new("/dd/shows/DEV01");
+        plan.set_vars.insert("DD_SHOW".to_string(), "DEV01".to_string());
+        plan.unset_vars.push("DD_SHOT".to_string());
+        plan.set_aliases.insert("cs".to_string(), PathBuf::from("/dd/shows/DEV01/SHARED"));
+        plan.clear_aliases.push("cw".to_string());
+        plan
+    }
+
+    #[test]
+    fn can_render_plan_for_bash() {
+        let plan = build_plan();
+        let script = plan.render(&bash::Shell::new());
+        assert_eq!(script, "unset DD_SHOT;export DD_SHOW=DEV01;unalias cw;alias cs='cd /dd/shows/DEV01/SHARED';\
+cd /dd/shows/DEV01;\necho 'Changed Directory To: /dd/shows/DEV01'\n\n");
+    }
+
+    #[test]
+    fn render_reports_ancestor_outcome() {
+        let mut plan = build_plan();
+        plan.outcome = GoOutcome::Ancestor{ requested: PathBuf::from("/dd/shows/DEV01/RD") };
+        let script = plan.render(&bash::Shell::new());
+        assert!(script.ends_with("echo 'Changed Directory To: /dd/shows/DEV01 (/dd/shows/DEV01/RD does not exist)'\n\n"), "{}", script);
+        assert!(plan.to_json().unwrap().contains(r#""outcome":{"kind":"ancestor","requested":"/dd/shows/DEV01/RD"}"#));
+    }
+
+    #[test]
+    fn can_render_plan_for_tcsh() {
+        let plan = build_plan();
+        let script = plan.render(&tcsh::Shell::new());
+        assert!(script.starts_with("unsetenv DD_SHOT;setenv DD_SHOW DEV01;unalias cw;alias cs 'cd /dd/shows/DEV01/SHARED';"));
+    }
+
+    #[test]
+    fn env_does_not_change_directory() {
+        let plan = build_plan();
+        let script = plan.render_as(&EnvFormat::from_str("bash").unwrap()).unwrap();
+        assert_eq!(script, "unset DD_SHOT;export DD_SHOW=DEV01;unalias cw;alias cs='cd /dd/shows/DEV01/SHARED';");
+    }
+
+    #[test]
+    fn can_render_dotenv() {
+        let mut plan = build_plan();
+        plan.set_vars.insert("DD_WORK".to_string(), "my \"$work\"".to_string());
+        assert_eq!(plan.to_dotenv(), "DD_SHOW=DEV01\nDD_WORK=\"my \\\"\\$work\\\"\"\n");
+    }
+
+    #[test]
+    fn can_round_trip_json() {
+        let plan = build_plan();
+        let json = plan.to_json().unwrap();
+        let plan2: GoPlan = serde_json::from_str(&json).unwrap();
+        assert_eq!(plan, plan2);
+    }
+
+    #[test]
+    fn can_parse_format() {
+        assert_eq!(GoFormat::from_str("JSON").unwrap(), GoFormat::Json);
+        assert_eq!(GoFormat::from_str("shell").unwrap(), GoFormat::Shell);
+        assert!(GoFormat::from_str("xml").is_err());
+        assert_eq!(EnvFormat::from_str("tcsh").unwrap(), EnvFormat::Shell(SupportedShell::Tcsh));
+        assert_eq!(EnvFormat::from_str("dotenv").unwrap(), EnvFormat::Dotenv);
+        assert!(EnvFormat::from_str("xml").is_err());
+    }
+}
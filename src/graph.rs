@@ -1,10 +1,14 @@
 use crate::{ 
+    Address,
+    cli,
     constants,
+    Dispatch,
     Node, 
     ReturnValue, 
     NIndex, 
     NodePath, 
     JSPError, 
+    Levels,
     jspt::{
         JGraphKeyMap, 
         RegexMap, 
@@ -15,7 +19,7 @@ use crate::{
 #[allow(unused_imports)]
 use log::{debug, trace};
 use petgraph::{ graph::{ DefaultIx, NodeIndex}, visit::IntoNodeReferences };
-use std::{ cell::RefCell, env, fs::File, rc::Rc, io::{BufReader}, path::{Path, PathBuf}};
+use std::{ env, fs::File, io::{BufReader}, path::{Path, PathBuf}};
 
 
 /// Define a type alias for the type of graph we will be using.
@@ -80,25 +84,102 @@ where
     // we have to drop the first item, which is the first "/"
     it.next();
 
-    let level: u8 = 0;
     let root_index = graph.node_references().next().unwrap().0;
     // we store the first index as we will be asking for its children, and
     // we both need it to be present and know that it will match all future
     // queries.
-    let indices = Vec::new();//vec![root_index]; 
-    let result = validate_path_recurse(it, &graph, root_index, level, Rc::new(RefCell::new(indices)));
+    let mut vals = validate_from(it, root_index, path.as_ref(), graph)?;
+    vals.insert(0, root_index);
+    Ok(NodePath::new(&graph).replace_nodes_unchecked(vals))
+}
+
+/// Determine if the provided relative path is valid, starting from the supplied node. 
+///
+/// # Parameters
+/// * `path` - a &str, String, Path, or PathBuf representing a candidate path, 
+///            relative to the `start` node
+/// * `start` - The NIndex of the node which the path is relative to
+/// * `graph` - a reference to the JGraph
+///
+/// # Returns
+///
+/// A NodePath of the nodes matched by the components of the path, not including
+/// the `start` node, if successful. Otherwise, a JSPError.
+///
+/// # Example
+///
+/// ```
+/// use jsp::{graph::testdata::build_graph, validate_path, validate_path_from};
+///
+/// let graph = build_graph();
+/// let shot = validate_path("/dd/shows/DEV01/RD/9999", &graph).unwrap();
+/// let rel = validate_path_from("user/work.jgerber", shot.nindex().unwrap(), &graph);
+/// assert_eq!(rel.unwrap().len(), 2);
+/// ```
+pub fn validate_path_from<'a, I>(path: I, start: NIndex, graph: &'a JGraph) 
+-> Result<NodePath<'a>, JSPError> 
+where
+    I: AsRef<Path> + std::fmt::Debug 
+{
+    if path.as_ref().has_root() {
+        return Err(JSPError::GeneralError(format!("validate_path_from(...) requires a relative path. Received {:?}", path)));
+    }
+    let vals = validate_from(path.as_ref().iter(), start, path.as_ref(), graph)?;
+    Ok(NodePath::new(&graph).replace_nodes_unchecked(vals))
+}
+
+/// Determine if the provided relative path is valid, starting from the node named 
+/// `name`. If more than one node shares the name, each is tried in turn, and the 
+/// first successful match is returned.
+///
+/// # Parameters
+/// * `path` - a &str, String, Path, or PathBuf representing a candidate path,
+///            relative to the named node
+/// * `name` - The name of the node which the path is relative to
+/// * `graph` - a reference to the JGraph
+///
+/// # Returns
+///
+/// A tuple of the NIndex of the named node, and the NodePath of the nodes matched
+/// by the components of the path, if successful. Otherwise, a JSPError. If validation
+/// fails for every node sharing the name, the deepest failure is returned.
+pub fn validate_path_from_name<'a, I>(path: I, name: &str, graph: &'a JGraph) 
+-> Result<(NIndex, NodePath<'a>), JSPError> 
+where
+    I: AsRef<Path> + std::fmt::Debug 
+{
+    let mut failure: Option<JSPError> = None;
+    for (idx, node) in graph.node_references() {
+        if node.name() != name { continue; }
+        match validate_path_from(path.as_ref(), idx, graph) {
+            Ok(nodepath) => return Ok((idx, nodepath)),
+            Err(e) => {
+                let deeper = match (&failure, &e) {
+                    (Some(JSPError::ValidationFailureFor{depth: prev, ..}), JSPError::ValidationFailureFor{depth, ..}) => depth > prev,
+                    (None, _) => true,
+                    _ => false,
+                };
+                if deeper { failure = Some(e); }
+            }
+        }
+    }
+    Err(failure.unwrap_or_else(|| JSPError::FindFailure(format!("No node named '{}' in template", name))))
+}
+
+// Validate the components of a path starting at the supplied node, returning the
+// indices of the matched nodes, not including the starting node.
+fn validate_from(it: std::path::Iter, start: NIndex, path: &Path, graph: &JGraph) 
+-> Result<Vec<NIndex>, JSPError> 
+{
+    let level: u8 = 0;
+    let result = validate_path_recurse(it, &graph, start, level);
     match result {
-        ReturnValue::Success(vals) => {
-            let mut vals = Rc::try_unwrap(vals)
-                          .unwrap()
-                          .into_inner();
-            //log::debug!("vals: {:?}", vals);
-            vals.push(root_index); // now that we are reversing, we need to push this onto the end
+        ReturnValue::Success(mut vals) => {
             vals.reverse();
-            Ok(NodePath::new(&graph).replace_nodes_unchecked(vals))
+            Ok(vals)
         },
         ReturnValue::Failure{entry, node, depth} => {
-            Err(JSPError::ValidationFailureFor{path: path.as_ref().to_path_buf(), entry, node, depth})
+            Err(JSPError::ValidationFailureFor{path: path.to_path_buf(), entry, node, depth})
         }
     }
 }
@@ -116,8 +197,9 @@ fn get_template_from_env_or_exit() -> Result<PathBuf,JSPError> {
     }
 }
 
+/// Retrieve the path to the template named by the JSP_PATH environment variable.
 #[inline]
-fn get_template_from_env() -> Result<PathBuf, JSPError> {
+pub fn get_template_from_env() -> Result<PathBuf, JSPError> {
     let jsp_path = env::var(constants::JSP_PATH)?;
     log::trace!("expanding tilde for {:?}", jsp_path);
     let jsp_path = shellexpand::tilde(jsp_path.as_str());
@@ -135,6 +217,88 @@ fn get_template_from_env() -> Result<PathBuf, JSPError> {
     Ok(jsp_path)
 }
 
+/// Retrieve the `etc` directory of the show described by `term`, which holds the 
+/// show's template. The show is located using the template named by the JSP_PATH 
+/// environment variable.
+///
+/// # Parameters
+/// * `term` - A levelspec or address, of which only the top level is used, or a path within the show
+/// * `full_path` - explicitly declare that `term` is a path
+/// * `fuzzy` - match a partial or abbreviated top level against the existing shows
+pub fn get_show_etc_dir(term: &str, full_path: bool, fuzzy: bool) -> Result<PathBuf, JSPError> {
+    let (graph, keymap, _regexmap) = get_graph(None)?;
+    get_show_etc_dir_from(term, full_path, fuzzy, &graph, &keymap)
+}
+
+/// Retrieve the `etc` directory of the show described by `term`, locating the show
+/// using the supplied graph, as loaded from the template named by the JSP_PATH 
+/// environment variable.
+///
+/// # Parameters
+/// * `term` - A levelspec or address, of which only the top level is used, or a path within the show
+/// * `full_path` - explicitly declare that `term` is a path
+/// * `fuzzy` - match a partial or abbreviated top level against the existing shows
+/// * `graph` - reference to the JGraph
+/// * `keymap` - reference to the keymap of the graph
+pub fn get_show_etc_dir_from(
+    term: &str, 
+    full_path: bool, 
+    fuzzy: bool, 
+    graph: &JGraph, 
+    keymap: &JGraphKeyMap
+) -> Result<PathBuf, JSPError> {
+    let levels = Levels::of(graph);
+    let top = levels.top().ok_or_else(|| JSPError::TemplateError("template declares no levels".to_string()))?;
+    let term = if full_path || (term.contains('/') && Address::parse(term).is_none()) {
+        term.to_string()
+    } else {
+        // only the top level of the levelspec is required to find the show
+        let levelspec = Address::parse(term).map(|address| address.levelspec).unwrap_or_else(|| term.to_string());
+        if fuzzy {
+            // the partial top level is resolved by validpath_from_terms
+            levelspec.split('.').next().unwrap_or("").to_string()
+        } else {
+            levels.parse(&levelspec)?.remove(0)
+        }
+    };
+    let mut validpath = cli::validpath_from_terms(vec![term], graph, false, full_path, fuzzy)?;
+    let idx = keymap.get(top).ok_or_else(|| JSPError::TemplateError(format!("template has no {} node", top)))?;
+    log::trace!("got index {:?}",idx );
+    validpath.remove_past(idx)?;
+
+    let mut pathbuf = validpath.pathbuf();
+    pathbuf.push("etc");
+    Ok(pathbuf)
+}
+
+/// Retrieve the template of the show containing `path`, from the show's `etc` 
+/// directory, locating the show using the supplied graph, as loaded from the 
+/// template named by the JSP_PATH environment variable. Unlike `get_show_etc_dir`,
+/// `path` need not be a valid location itself (eg the current working directory 
+/// may be untracked), as the nearest valid ancestor is used.
+///
+/// # Parameters
+/// * `path` - An absolute path within the show
+/// * `graph` - reference to the JGraph
+///
+/// # Returns
+/// Some path to the show's template, if `path` is within a show which has one.
+/// Otherwise, None, in which case the template named by JSP_PATH applies.
+pub fn get_show_template_for_path(path: &Path, graph: &JGraph) -> Option<PathBuf> {
+    let levels = Levels::of(graph);
+    let top = levels.top()?;
+    let (ancestor, nodepath) = path.ancestors()
+                                   .find_map(|ancestor| validate_path(ancestor, graph).ok().map(|np| (ancestor, np)))?;
+    // the nodepath starts at the root, as the path's components do
+    let depth = nodepath.iter().position(|node| node.name() == top)?;
+    let template = ancestor.iter()
+                           .take(depth + 1)
+                           .collect::<PathBuf>()
+                           .join("etc")
+                           .join(constants::JSP_NAME);
+    if template.is_file() { Some(template) } else { None }
+}
+
 // Never Used
 // #[inline]
 // fn open_template(template: &Path) -> File {
@@ -147,13 +311,32 @@ fn get_template_from_env() -> Result<PathBuf, JSPError> {
 //     }
 // }
 
+/// Build the dispatch index for every node in the graph. The index allows
+/// `validate_path` and `find` to select the children matching a path 
+/// component without testing each child in turn. The levels of the graph are
+/// recorded on the root alongside it, so that `Levels::of` need not walk the
+/// graph. It should be rebuilt if the graph is modified after loading.
+///
+/// # Parameters
+/// * `graph` - mutable reference to the JGraph
+pub fn build_dispatch_index(graph: &mut JGraph) {
+    let indices = graph.node_indices().collect::<Vec<NIndex>>();
+    for idx in indices.iter() {
+        let dispatch = Dispatch::new(*idx, graph);
+        graph[*idx].set_dispatch(dispatch);
+    }
+    if let Some(root) = indices.first() {
+        let levels = Levels::from_graph(graph);
+        graph[*root].set_levels(levels);
+    }
+}
+
 // helper recursive function
 fn validate_path_recurse(
     mut path: std::path::Iter,
     graph: &JGraph,
     parent: NodeIndex<DefaultIx>,
     level: u8,
-    indices: Rc<RefCell<Vec<NIndex>>>
 ) -> ReturnValue {
     //log::warn!("parent {:?}",parent);
     let mut result: Option<ReturnValue> = None;
@@ -161,17 +344,36 @@ fn validate_path_recurse(
     let component = path.next();
     match component {
         Some(val) => {
-            let mut cnt = 0;
-            for n in graph.neighbors(parent) {
-                let node = &graph[n];
-                log::trace!("testing {:?} against {:?}", val, node);
-                if node == val {
-                    trace!("MATCH");
-                    let r = validate_path_recurse(path.clone(), graph, n, level, indices.clone());
-                    if r.is_success() {
-                        indices.borrow_mut().push(n);
+            let dispatch = graph[parent].dispatch();
+            // retrieve the children matching the component, using the dispatch index if
+            // it has been built. Otherwise, test each child in turn.
+            let (cnt, children) = match val.to_str() {
+                Some(entry) if dispatch.is_indexed() => {
+                    (dispatch.child_count(), dispatch.matching(entry, graph))
+                }
+                _ => {
+                    let mut cnt = 0;
+                    let mut children = Vec::new();
+                    for n in graph.neighbors(parent) {
+                        log::trace!("testing {:?} against {:?}", val, &graph[n]);
+                        if &graph[n] == val {
+                            children.push(n);
+                        }
+                        cnt += 1;
+                    }
+                    (cnt, children)
+                }
+            };
+            for n in children {
+                trace!("MATCH {:?} {:?}", val, n);
+                let r = validate_path_recurse(path.clone(), graph, n, level);
+                match r {
+                    ReturnValue::Success(mut indices) => {
+                        // indices are accumulated from the leaf up, and reversed by the caller
+                        indices.push(n);
                         return ReturnValue::Success(indices);
-                    } else {
+                    }
+                    _ => {
                         match result {
                             None => result = Some(r),
                             Some(ref val) => {
@@ -181,20 +383,16 @@ fn validate_path_recurse(
                             }
                         }
                     }
-                } else {
-                    trace!("NO MATCH");
                 }
-                cnt += 1;
             }
-            // cannot find a way to get number of children for node any other way.
             // we assume that if we have made it this far, and there are no children,
             // we are successful. This allows the path to extend beyond the graph.
             if cnt == 0 {
-                return ReturnValue::Success(indices);
+                return ReturnValue::Success(Vec::new());
             }
         }
         None => {
-            return ReturnValue::Success(indices);
+            return ReturnValue::Success(Vec::new());
         }
     }
     if result.is_some() {
@@ -206,8 +404,18 @@ fn validate_path_recurse(
 
 pub mod testdata {
     use crate::{ JGraph, Node, jspnode, Regexp, NodeType, EntryType };
+    use super::build_dispatch_index;
 
+    /// Build the test graph, including the dispatch index
     pub fn build_graph() -> JGraph {
+        let mut graph = build_graph_unindexed();
+        build_dispatch_index(&mut graph);
+        graph
+    }
+
+    /// Build the test graph without the dispatch index. The graph walkers fall
+    /// back to testing each child in turn.
+    pub fn build_graph_unindexed() -> JGraph {
         let mut graph = JGraph::new();
 
         let root = graph.add_node(Node::new_root());
@@ -487,6 +695,8 @@ pub mod testdata {
 mod tests {
     use super::*;
     use super::testdata::build_graph;
+    use crate::{jspnode, EntryType, Level, NodeType, Regexp, testutil::{graph_to, TempDir}};
+    use std::fs;
 
     #[test]
     fn path_extends_beyond_graph() {
@@ -509,4 +719,64 @@ mod tests {
         let p = "/dd/shows/DEV01/RD/9999/FOO/SHARED/MODEL";
         assert!(validate_path(p, &tgraph).is_err());
     }
+
+    #[test]
+    fn indexed_and_unindexed_graphs_agree() {
+        let indexed = build_graph();
+        let unindexed = testdata::build_graph_unindexed();
+        let paths = [
+            "/dd/shows/DEV01",
+            "/dd/shows/DEV01/RD/9999/user/work.jgerber",
+            "/dd/shows/DEV01/SHARED/MODEL/char/model/bob",
+            "/dd/shows/DEV01/CONFORM/SHARED/MODEL",
+            "/dd/shows/DEV01/RD/9999/FOO/SHARED/MODEL",
+            "/dd/shows/SHARED",
+        ];
+        for p in paths.iter() {
+            let a = validate_path(p, &indexed).map(|np| np.nodes);
+            let b = validate_path(p, &unindexed).map(|np| np.nodes);
+            assert_eq!(a, b, "{}", p);
+        }
+    }
+
+    #[test]
+    fn relative_path_validates_from_node() {
+        let tgraph = build_graph();
+        let shot = validate_path("/dd/shows/DEV01/RD/9999", &tgraph).unwrap();
+        let rel = validate_path_from("SHARED/MODEL/char", shot.nindex().unwrap(), &tgraph).unwrap();
+        let names = rel.iter().map(|n| n.name().to_string()).collect::<Vec<String>>();
+        assert_eq!(names, vec!["SHARED", "dept", "category"]);
+        assert!(validate_path_from("FOO/MODEL", shot.nindex().unwrap(), &tgraph).is_err());
+        assert!(validate_path_from("/SHARED", shot.nindex().unwrap(), &tgraph).is_err());
+    }
+
+    #[test]
+    fn can_find_show_template_for_path() {
+        let dir = TempDir::new("show_template");
+        let (mut graph, parent) = graph_to(dir.path());
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[0-9]*$"));
+        let etc = graph.add_node(jspnode!("etc"));
+        graph.extend_with_edges(&[(parent, show), (show, etc)]);
+        let root = graph.node_indices().next().unwrap();
+        graph[root].set_levels(Levels::new(vec![Level::new("show", None)]));
+
+        dir.mkdirs(&["DEV01/etc", "DEV01/untracked/dir", "DEV02/etc"]);
+        fs::write(dir.join("DEV01/etc").join(constants::JSP_NAME), "").unwrap();
+        let template = Some(dir.join("DEV01/etc").join(constants::JSP_NAME));
+        assert_eq!(get_show_template_for_path(&dir.join("DEV01"), &graph), template);
+        // the path need not be valid, as long as it is within the show
+        assert_eq!(get_show_template_for_path(&dir.join("DEV01/untracked/dir"), &graph), template);
+        // shows without a template of their own, and paths above the shows, have none
+        assert_eq!(get_show_template_for_path(&dir.join("DEV02/etc"), &graph), None);
+        assert_eq!(get_show_template_for_path(dir.path(), &graph), None);
+    }
+
+    #[test]
+    fn relative_path_validates_from_name() {
+        let tgraph = build_graph();
+        let (idx, rel) = validate_path_from_name("user/work.jgerber", "shot", &tgraph).unwrap();
+        assert_eq!(tgraph[idx].name(), "shot");
+        assert_eq!(rel.len(), 2);
+        assert!(validate_path_from_name("user/work.jgerber", "nonexistent", &tgraph).is_err());
+    }
 }
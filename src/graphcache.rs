@@ -0,0 +1,122 @@
+//! Cache the graph loaded from a template as json, so that commands which run
+//! frequently, like `jsp prompt`, may skip parsing the template. A cache entry 
+//! is only used while it is at least as new as the template it was loaded from.
+//!
+//! Reading the cache never writes to disk. The cache is written explicitly, via
+//! `store` (eg `jsp cache`).
+use crate::{build_dispatch_index, constants, get_graph, JGraph, JSPError};
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Retrieve the cache directory. This is JSP_CACHE_DIR if set, or ~/.cache/jsp
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var(constants::JSP_CACHE_DIR) {
+        return Some(PathBuf::from(dir));
+    }
+    env::var("HOME").ok().map(|home| Path::new(&home).join(".cache").join("jsp"))
+}
+
+/// Retrieve the path of the cache entry for the supplied template
+pub fn cache_path(template: &Path) -> Option<PathBuf> {
+    let name = template.to_string_lossy().trim_start_matches('/').replace('/', "%");
+    cache_dir().map(|dir| dir.join(format!("{}.json", name)))
+}
+
+/// Load the graph for `template` from the cache, if there is a cache entry which 
+/// is at least as new as the template.
+pub fn load(template: &Path) -> Option<JGraph> {
+    load_from(&cache_path(template)?, template)
+}
+
+/// Load the graph for `template` from the cache entry `cache`, if it is at least as 
+/// new as the template.
+pub fn load_from(cache: &Path, template: &Path) -> Option<JGraph> {
+    let template_modified = fs::metadata(template).and_then(|m| m.modified()).ok()?;
+    let cache_modified = fs::metadata(cache).and_then(|m| m.modified()).ok()?;
+    if cache_modified < template_modified {
+        log::debug!("graphcache::load_from(...) {:?} is stale", cache);
+        return None;
+    }
+    let file = File::open(cache).ok()?;
+    match serde_json::from_reader::<_, JGraph>(BufReader::new(file)) {
+        Ok(mut graph) => {
+            // the dispatch index is not serialized
+            build_dispatch_index(&mut graph);
+            Some(graph)
+        }
+        Err(e) => {
+            log::debug!("graphcache::load_from(...) unable to read {:?}: {}", cache, e);
+            None
+        }
+    }
+}
+
+/// Write the graph for `template` to the cache, returning the path of the cache entry.
+pub fn store(template: &Path, graph: &JGraph) -> Result<PathBuf, JSPError> {
+    let cache = cache_path(template)
+                    .ok_or_else(|| JSPError::GeneralError("Unable to determine the cache directory".to_string()))?;
+    store_to(&cache, graph)?;
+    Ok(cache)
+}
+
+/// Write the graph to the cache entry `cache`. The entry is written to a temporary
+/// file first, so that readers never see a partial entry.
+pub fn store_to(cache: &Path, graph: &JGraph) -> Result<(), JSPError> {
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = cache.with_extension(format!("json.{}", std::process::id()));
+    {
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer(&mut writer, graph).map_err(|e| JSPError::GeneralError(e.to_string()))?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp, cache)?;
+    Ok(())
+}
+
+/// Retrieve the graph for `template`, from the cache if possible, and otherwise by
+/// parsing the template. The cache is not updated.
+pub fn get_graph_cached(template: &Path) -> Result<JGraph, JSPError> {
+    if let Some(graph) = load(template) {
+        return Ok(graph);
+    }
+    let (graph, _keymap, _regexmap) = get_graph(Some(template.to_path_buf()))?;
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::testdata::build_graph, testutil::TempDir};
+
+    #[test]
+    fn can_store_and_load_graph() {
+        let dir = TempDir::new("graphcache_test");
+        let template = dir.join("template.jspt");
+        fs::write(&template, "").unwrap();
+        let cache = dir.join("cache").join("template.json");
+
+        let graph = build_graph();
+        store_to(&cache, &graph).unwrap();
+        let loaded = load_from(&cache, &template);
+
+        let loaded = loaded.expect("cache entry should be fresh");
+        assert_eq!(loaded.node_count(), graph.node_count());
+        assert_eq!(loaded.edge_count(), graph.edge_count());
+        for idx in graph.node_indices() {
+            assert_eq!(loaded[idx], graph[idx]);
+            assert!(loaded[idx].dispatch().is_indexed());
+        }
+    }
+
+    #[test]
+    fn missing_cache_is_none() {
+        let dir = TempDir::new("graphcache_missing");
+        assert!(load_from(&dir.join("no_such_cache.json"), dir.path()).is_none());
+    }
+}
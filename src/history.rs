@@ -0,0 +1,260 @@
+//! Per user navigation history. Each successful `jsp go` appends the location
+//! to the history file - JSP_HISTORY if set, otherwise `~/.jsp_history` - as a 
+//! line of json. The history backs `jspgo -` (the previous location), 
+//! `jspgo -N` (the Nth previous location), and `jsp history`. As several 
+//! shells may navigate at once, the history file is updated under an advisory
+//! lock, and locations are counted back from the shell's current directory
+//! rather than from the most recent entry, which another shell may have recorded.
+use crate::{constants, JSPError};
+use nix::fcntl::{flock, FlockArg};
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+/// A location visited via `jsp go`
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the epoch
+    pub time: i64,
+    /// The location
+    pub path: PathBuf,
+    /// The levelspec of the location, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levelspec: Option<String>,
+}
+
+impl HistoryEntry {
+    /// New up a HistoryEntry for a visit made now
+    pub fn new<I: Into<PathBuf>>(path: I, levelspec: Option<String>) -> Self {
+        Self {
+            time: chrono::Utc::now().timestamp(),
+            path: path.into(),
+            levelspec,
+        }
+    }
+}
+
+/// The navigation history, oldest entry first.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct History {
+    file: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Retrieve the path to the history file. This is JSP_HISTORY if set, or 
+    /// ~/.jsp_history
+    pub fn history_file() -> Option<PathBuf> {
+        if let Ok(file) = env::var(constants::JSP_HISTORY) {
+            return Some(PathBuf::from(file));
+        }
+        env::var("HOME").ok().map(|home| Path::new(&home).join(constants::JSP_HISTORY_FILE))
+    }
+
+    /// Load the user's history.
+    pub fn load() -> Result<Self, JSPError> {
+        let file = Self::history_file()
+                    .ok_or_else(|| JSPError::GeneralError("Unable to determine the history file".to_string()))?;
+        Self::from_file(file)
+    }
+
+    /// Load the history from `file`. A missing file is an empty history, and 
+    /// lines which cannot be read are skipped.
+    pub fn from_file<I: Into<PathBuf>>(file: I) -> Result<Self, JSPError> {
+        let file = file.into();
+        let mut entries = Vec::new();
+        if file.exists() {
+            let reader = BufReader::new(File::open(&file)?);
+            for line in reader.lines() {
+                match serde_json::from_str::<HistoryEntry>(&line?) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => log::warn!("History::from_file(...) skipping entry in {:?}: {}", file, e),
+                }
+            }
+        }
+        Ok(Self{ file, entries })
+    }
+
+    /// The entries, oldest first
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Retrieve the entry `n` locations back from `cwd`. `previous(1, cwd)` is the
+    /// most recently visited location other than `cwd`. Visits to `cwd`, and repeat
+    /// visits to the same location, are not counted.
+    pub fn previous(&self, n: usize, cwd: &Path) -> Option<&HistoryEntry> {
+        self.back_counts(cwd).iter()
+            .position(|count| *count == Some(n))
+            .map(|idx| &self.entries[idx])
+    }
+
+    /// Retrieve, for each entry oldest first, the `n` for which `previous(n, cwd)`
+    /// returns it, or None if it is not counted.
+    pub fn back_counts(&self, cwd: &Path) -> Vec<Option<usize>> {
+        let mut counts = vec![None; self.entries.len()];
+        let mut last = cwd;
+        let mut n = 0;
+        for (idx, entry) in self.entries.iter().enumerate().rev() {
+            if entry.path != cwd && entry.path != last {
+                n += 1;
+                counts[idx] = Some(n);
+            }
+            last = &entry.path;
+        }
+        counts
+    }
+
+    /// Record a visit and save the history. Revisiting the most recent location
+    /// is not recorded again. The oldest entries are dropped to keep the history
+    /// to JSP_HISTORY_MAX entries. The history is re-read under the lock first,
+    /// so that visits recorded by other shells since it was loaded are kept.
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<(), JSPError> {
+        let _lock = self.lock()?;
+        self.entries = Self::from_file(&self.file)?.entries;
+        if self.entries.last().map(|last| last.path == entry.path).unwrap_or(false) {
+            return Ok(());
+        }
+        self.entries.push(entry);
+        if self.entries.len() > constants::JSP_HISTORY_MAX {
+            let excess = self.entries.len() - constants::JSP_HISTORY_MAX;
+            self.entries.drain(..excess);
+        }
+        self.write()
+    }
+
+    /// Write the history to its file, under the lock. 
+    pub fn save(&self) -> Result<(), JSPError> {
+        let _lock = self.lock()?;
+        self.write()
+    }
+
+    // Take an exclusive advisory lock on the history, which is released when the 
+    // returned file is dropped. A separate lock file is locked, as `write` replaces
+    // the history file.
+    fn lock(&self) -> Result<File, JSPError> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lockfile = OpenOptions::new().create(true).write(true).open(self.file.with_extension("lock"))?;
+        flock(lockfile.as_raw_fd(), FlockArg::LockExclusive)?;
+        Ok(lockfile)
+    }
+
+    // Write the history to its file. The file is written to a temporary file
+    // first, so that concurrent readers never see a partial history.
+    fn write(&self) -> Result<(), JSPError> {
+        let tmp = self.file.with_extension(format!("tmp.{}", std::process::id()));
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            for entry in &self.entries {
+                serde_json::to_writer(&mut writer, entry).map_err(|e| JSPError::GeneralError(e.to_string()))?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        fs::rename(&tmp, &self.file)?;
+        Ok(())
+    }
+}
+
+/// Parse a reference to a previous location - `-` for the previous location, or
+/// `-N` for the Nth previous location. Returns None if `term` is not a reference.
+pub fn parse_back_reference(term: &str) -> Option<usize> {
+    if term == "-" {
+        return Some(1);
+    }
+    if !term.starts_with('-') {
+        return None;
+    }
+    match term[1..].parse::<usize>() {
+        Ok(n) if n > 0 => Some(n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn can_parse_back_reference() {
+        assert_eq!(parse_back_reference("-"), Some(1));
+        assert_eq!(parse_back_reference("-3"), Some(3));
+        assert_eq!(parse_back_reference("-0"), None);
+        assert_eq!(parse_back_reference("-f"), None);
+        assert_eq!(parse_back_reference("dev01"), None);
+    }
+
+    #[test]
+    fn can_record_and_reload_history() {
+        let tmp = TempDir::new("history_record");
+        let file = tmp.join("history");
+        let mut history = History::from_file(&file).unwrap();
+        assert!(history.entries().is_empty());
+        history.record(HistoryEntry::new("/dd/shows/DEV01", Some("DEV01".to_string()))).unwrap();
+        history.record(HistoryEntry::new("/dd/shows/DEV01/RD", Some("DEV01.RD".to_string()))).unwrap();
+        history.record(HistoryEntry::new("/dd/shows/DEV01/RD", Some("DEV01.RD".to_string()))).unwrap();
+        history.record(HistoryEntry::new("/dd/shows/DEV01/RD/9999", Some("DEV01.RD.9999".to_string()))).unwrap();
+
+        let history = History::from_file(&file).unwrap();
+        assert_eq!(history.entries().len(), 3);
+        let cwd = Path::new("/dd/shows/DEV01/RD/9999");
+        assert_eq!(history.previous(1, cwd).map(|e| e.path.clone()), Some(PathBuf::from("/dd/shows/DEV01/RD")));
+        assert_eq!(history.previous(2, cwd).map(|e| e.levelspec.clone()), Some(Some("DEV01".to_string())));
+        assert_eq!(history.previous(3, cwd), None);
+    }
+
+    #[test]
+    fn previous_is_counted_from_cwd() {
+        let tmp = TempDir::new("history_cwd");
+        let file = tmp.join("history");
+        let mut history = History::from_file(&file).unwrap();
+        // one shell visits DEV01 then DEV02, while another visits DEV03
+        for path in &["/dd/shows/DEV01", "/dd/shows/DEV02", "/dd/shows/DEV03", "/dd/shows/DEV02"] {
+            history.record(HistoryEntry::new(*path, None)).unwrap();
+        }
+        let cwd = Path::new("/dd/shows/DEV02");
+        assert_eq!(history.previous(1, cwd).map(|e| e.path.clone()), Some(PathBuf::from("/dd/shows/DEV03")));
+        assert_eq!(history.previous(2, cwd).map(|e| e.path.clone()), Some(PathBuf::from("/dd/shows/DEV01")));
+        assert_eq!(history.previous(3, cwd), None);
+        assert_eq!(history.back_counts(cwd), vec![Some(2), None, Some(1), None]);
+        // the most recent entry is not the current directory of this shell
+        let cwd = Path::new("/dd/shows/DEV03");
+        assert_eq!(history.previous(1, cwd).map(|e| e.path.clone()), Some(PathBuf::from("/dd/shows/DEV02")));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let tmp = TempDir::new("history_bounded");
+        let file = tmp.join("history");
+        let mut history = History::from_file(&file).unwrap();
+        for i in 0..constants::JSP_HISTORY_MAX + 5 {
+            history.entries.push(HistoryEntry::new(format!("/dd/shows/{}", i), None));
+        }
+        history.save().unwrap();
+        history.record(HistoryEntry::new("/dd/shows/last", None)).unwrap();
+        assert_eq!(history.entries().len(), constants::JSP_HISTORY_MAX);
+        assert_eq!(history.entries().last().unwrap().path, PathBuf::from("/dd/shows/last"));
+    }
+
+    #[test]
+    fn concurrent_records_are_merged() {
+        let tmp = TempDir::new("history_merged");
+        let file = tmp.join("history");
+        let mut first = History::from_file(&file).unwrap();
+        let mut second = History::from_file(&file).unwrap();
+        first.record(HistoryEntry::new("/dd/shows/DEV01", None)).unwrap();
+        second.record(HistoryEntry::new("/dd/shows/DEV02", None)).unwrap();
+
+        let history = History::from_file(&file).unwrap();
+        let paths = history.entries().iter().map(|e| e.path.clone()).collect::<Vec<PathBuf>>();
+        assert_eq!(paths, vec![PathBuf::from("/dd/shows/DEV01"), PathBuf::from("/dd/shows/DEV02")]);
+    }
+}
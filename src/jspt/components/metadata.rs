@@ -3,6 +3,9 @@
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum MetadataComponent {
     Autocreate,
+    Level,
+    /// A short name by which the node may be addressed (eg `dev01:assetdev/BOBCHAR`)
+    Entry(String),
     Volume,
     Permissions(String),
     EnvVarName(String),
@@ -10,6 +13,12 @@ pub enum MetadataComponent {
     Group(String),
     /// Navalias takes the key, and optionally, a value
     NavAlias(String, Option<String>), 
+    /// Navalias with an explicit priority - `navalias(10): cs work.$USER`
+    PriorityNavAlias(String, Option<String>, i32),
+    /// An environment variable to export, and its value template - `env: DD_SHOT_ROOT=$path`
+    EnvExport(String, String),
+    /// An env file to load, relative to the node - `envfile: etc/show.env`
+    EnvFile(String),
     // Nom requires that all branches of certain 
     // matches have the same type, so I added 
     // Separator, even though it isn't really a 
@@ -25,6 +34,10 @@ pub enum MetadataComponent {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct JsptMetadata {
     autocreate: bool,
+    /// whether the node is a level of the job hierarchy (eg show, sequence, shot)
+    level: bool,
+    /// short name by which the node may be addressed, beneath a levelspec
+    entry: Option<String>,
     volume: bool,
     permissions: Option<String>,
     varname: Option<String>,
@@ -32,19 +45,28 @@ pub struct JsptMetadata {
     group: Option<String>,
     /// tuple of Keyname, and optionally, a value. Only necessary 
     /// if we need to define runtime variables (eg work.$user)
-    navalias: Option<(String, Option<String>)>
+    /// navigation aliases - name, optional value, and priority
+    navaliases: Vec<(String, Option<String>, i32)>,
+    /// environment variables to export - name and value template
+    env: Vec<(String, String)>,
+    /// env file to load, relative to the node's directory
+    envfile: Option<String>,
 }
 
 impl std::default::Default for JsptMetadata {
     fn default() -> JsptMetadata {
         Self {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: false,
             permissions: None,
             varname: None,
             owner: None,
             group: None, 
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
         }
     }
 }
@@ -59,12 +81,16 @@ impl JsptMetadata {
     /// and all of the optional terms being None. 
     pub fn is_empty(&self) -> bool {
         !self.autocreate && 
+        !self.level && 
+        self.entry.is_none() && 
         !self.volume  && 
         self.permissions.is_none() && 
         self.varname.is_none() && 
         self.owner.is_none() &&
         self.group.is_none() &&
-        self.navalias.is_none()
+        self.navaliases.is_empty() &&
+        self.env.is_empty() &&
+        self.envfile.is_none()
     }
 
     /// Set volume and get back moved self. This is designed to be used in 
@@ -119,6 +145,35 @@ impl JsptMetadata {
     pub fn is_autocreate(&self) -> bool {
         self.autocreate
     }
+
+    /// Set the level bool. Level indicates that the node is one of the 
+    /// levels of the job hierarchy (eg show, sequence, shot), which make
+    /// up a levelspec. 
+    pub fn set_level(mut self, level: bool) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Test to see if the metadata struct has level set to true.
+    pub fn is_level(&self) -> bool {
+        self.level
+    }
+
+    /// Set the entry name, given an Option wrapped type which implements Into<String>.
+    /// The entry name is a short name by which the node may be addressed beneath 
+    /// a levelspec (eg `dev01:assetdev/BOBCHAR`).
+    pub fn set_entry<T>(mut self, entry: Option<T>) -> Self 
+    where
+        T: Into<String> 
+    {
+        self.entry = entry.map(|x| x.into());
+        self
+    }
+
+    /// Retrieve the entry name as an Option<&str>
+    pub fn entry(&self) -> Option<&str> {
+        self.entry.as_ref().map(|x| &**x)
+    }
     /// Set permissions, passing in an Option of a type which we 
     /// can get a string from (via into). This method consumes and
     /// returns `self`, so it is convenient when using in a chained,
@@ -314,6 +369,7 @@ impl JsptMetadata {
     }
 
     /// Set `navalias` given an Option wrapped type which implements `Into<String>`.
+    /// This replaces any navaliases previously set or added.
     /// 
     /// # Parameters
     /// 
@@ -323,29 +379,78 @@ impl JsptMetadata {
     where
         T: Into<String>
     {
-        self.navalias = navalias.map(|(x,y)| {
-            match y {
-                Some(val) => (x.into(), Some(val.into())),
-                None => (x.into(), None)
-            }
-        });
+        self.navaliases = navalias.into_iter().map(|(x,y)| (x.into(), y.map(|val| val.into()), 0)).collect();
         self
     }
 
-    /// Retrieve a reference to `navalias` as an Option wrapped `&str`.
+    /// Add a navalias, with a priority, to any previously set.
+    /// 
+    /// # Parameters
+    /// 
+    /// * `name` - The name of the alias
+    /// * `value` - The optional value of the alias
+    /// * `priority` - The priority of the alias. Higher priorities win.
+    pub fn add_navalias<T>(mut self, name: T, value: Option<T>, priority: i32) -> Self 
+    where
+        T: Into<String>
+    {
+        self.navaliases.push((name.into(), value.map(|val| val.into()), priority));
+        self
+    }
+
+    /// Retrieve a reference to the first `navalias` as an Option wrapped `&str`.
     pub fn navalias(&self) -> Option<(&str, Option<&str>)> {
-        self.navalias.as_ref().map(|(x,y)| {
-            match y {
-                Some(ref val) => (&**x, Some(&**val)),
-                None => (&**x, None)
-            }
-        })
+        self.navaliases.first().map(|(x, y, _)| (&**x, y.as_ref().map(|val| &**val)))
     }
 
-    /// Retrieve the `navalias` as an Option wrapped String, leaving 
-    /// None in its place. 
+    /// Iterate over the navaliases, as tuples of name, optional value, and priority
+    pub fn navaliases(&self) -> impl Iterator<Item = (&str, Option<&str>, i32)> {
+        self.navaliases.iter().map(|(x, y, p)| (&**x, y.as_ref().map(|val| &**val), *p))
+    }
+
+    /// Add an environment variable to export, given its name and value template
+    /// 
+    /// # Parameters
+    /// 
+    /// * `name` - The name of the environment variable
+    /// * `value` - The value template (eg `$path`)
+    pub fn add_env<T>(mut self, name: T, value: T) -> Self 
+    where
+        T: Into<String>
+    {
+        self.env.push((name.into(), value.into()));
+        self
+    }
+
+    /// Iterate over the environment variables to export, as tuples of name and value template
+    pub fn env(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.env.iter().map(|(x, y)| (&**x, &**y))
+    }
+
+    /// Set the env file, given an Option wrapped type which implements Into<String>.
+    /// The path is relative to the node's directory (eg `etc/show.env`).
+    pub fn set_envfile<T>(mut self, envfile: Option<T>) -> Self 
+    where
+        T: Into<String>
+    {
+        self.envfile = envfile.map(|x| x.into());
+        self
+    }
+
+    /// Retrieve the env file as an Option<&str>
+    pub fn envfile(&self) -> Option<&str> {
+        self.envfile.as_ref().map(|x| &**x)
+    }
+
+    /// Retrieve the first `navalias` as an Option wrapped String, removing 
+    /// it from the metadata. 
     pub fn take_navalias(&mut self) -> Option<(String, Option<String>)> {
-        self.navalias.take()
+        if self.navaliases.is_empty() {
+            None
+        } else {
+            let (name, value, _) = self.navaliases.remove(0);
+            Some((name, value))
+        }
     }
 
 }
@@ -359,12 +464,16 @@ mod tests {
         let md = JsptMetadata::new();
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: false,
             permissions: None,
             varname: None,
             owner: None,
             group: None,
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
         };
         assert_eq!(md, expect);
     }
@@ -374,12 +483,16 @@ mod tests {
         let md = JsptMetadata::new().set_volume(true);
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: true,
             permissions: None,
             varname: None,
             owner: None,
             group: None, 
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
         };
         assert_eq!(md, expect);
     }
@@ -389,12 +502,16 @@ mod tests {
         let md = JsptMetadata::new().set_volume(true).set_owner(Some("jgerber"));
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: true,
             permissions: None,
             varname: None,
             owner: Some("jgerber".to_string()),
             group: None,
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
 
         };
         assert_eq!(md, expect);
@@ -405,12 +522,16 @@ mod tests {
         let md = JsptMetadata::new().set_volume(true).set_group(Some("cgi"));
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: true,
             permissions: None,
             varname: None,
             owner:None,
             group: Some("cgi".to_string()),
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
 
         };
         assert_eq!(md, expect);
@@ -421,12 +542,16 @@ mod tests {
         let md = JsptMetadata::new().set_volume(true).set_owner(Some("jgerber")).set_varname(Some("jg_show"));
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: true,
             permissions: None,
             varname: Some("jg_show".to_string()),
             owner: Some("jgerber".to_string()),
             group: None,
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
 
         };
         assert_eq!(md, expect);
@@ -442,12 +567,16 @@ mod tests {
 
         let expect = JsptMetadata {
             autocreate: false,
+            level: false,
+            entry: None,
             volume: true,
             permissions: Some("777".to_string()),
             varname: Some("jg_show".to_string()),
             owner: Some("jgerber".to_string()),
             group: None,
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
 
         };
         assert_eq!(md, expect);
@@ -465,12 +594,16 @@ mod tests {
 
         let expect = JsptMetadata {
             autocreate: true,
+            level: false,
+            entry: None,
             volume: true,
             permissions: Some("777".to_string()),
             varname: Some("jg_show".to_string()),
             owner: Some("jgerber".to_string()),
             group: None,
-            navalias: None,
+            navaliases: Vec::new(),
+            env: Vec::new(),
+            envfile: None,
 
         };
         assert_eq!(md, expect);
@@ -577,4 +710,15 @@ mod tests {
                     .set_navalias(Some(("cs", Some("work.$USER"))));
         assert_eq!(md.take_navalias(), Some(("cs".to_string(), Some("work.$USER".to_string()))));
     }
+
+    #[test]
+    fn can_add_navaliases() {
+        let md = JsptMetadata::new()
+                    .add_navalias("cs", Some("work.$USER"), 0)
+                    .add_navalias("today", Some("logs/$DATE"), 10);
+        assert_eq!(
+            md.navaliases().collect::<Vec<_>>(), 
+            vec![("cs", Some("work.$USER"), 0), ("today", Some("logs/$DATE"), 10)]
+        );
+    }
 }
\ No newline at end of file
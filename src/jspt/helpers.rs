@@ -195,6 +195,22 @@ mod navalias_str_test {
     }
 }
 
+/// Parser which parses navalias values. In addition to the navalias chars, values
+/// may contain path separators, dashes, and braced variables (eg `logs/${DATE}`)
+pub fn navalias_value(input: &str) -> IResult<&str, &str> {
+  input.split_at_position1_complete(|item| !(is_navalias_char(item) || ['/', '-', '{', '}'].contains(&item)), ErrorKind::Alpha)
+}
+
+#[cfg(test)]
+mod navalias_value_test {
+    use super::*;
+
+    #[test]
+    fn is_a_navalias_value_test() {
+        assert_eq!(navalias_value("logs/${DATE} ]"), Ok((" ]", "logs/${DATE}")));
+    }
+}
+
 // Parser which parses contigous regular expression characters, as defined by the is_regex_char. 
 // Note that we do not accept spaces, single or double quotes
 pub fn regex_str(input: &str) -> IResult<&str, &str> {
@@ -9,7 +9,7 @@ use crate::jspt::{
     Edge,
     JsptMetadata,
 };
-use crate::{JGraph, Navalias, NIndex, User, Node, Regexp, EntryType, NodeType, Metadata as JspMetadata };
+use crate::{graph, JGraph, Navalias, NIndex, User, Node, Regexp, EntryType, NodeType, Metadata as JspMetadata };
 use log;
 use std::{io::BufRead, collections::HashMap};
 
@@ -163,6 +163,8 @@ impl<'a> Loader<'a> {
                 }
             } 
         }
+        // now that the graph is complete, index each node's children
+        graph::build_dispatch_index(self.graph);
         Ok(())
     }
 
@@ -370,18 +372,26 @@ fn new_jsp_metadata( meta: &Option<JsptMetadata> ) -> JspMetadata {
         }
 
         jspmeta.set_autocreate(meta.is_autocreate());
+        jspmeta.set_level(meta.is_level());
+
+        if let Some(entry) = meta.entry() {
+            jspmeta.set_entry(Some(entry.to_string()));
+        }
         
-        if meta.navalias().is_some() {
-            let navalias =  meta.navalias().unwrap();
-            let navalias = match navalias.1 {
-                None => {
-                    Navalias::new_simple(navalias.0)
-                }
-                Some(ref value) => {
-                    Navalias::new_complex(navalias.0,value)
-                }
+        for (name, value, priority) in meta.navaliases() {
+            let navalias = match value {
+                None => Navalias::new_simple(name),
+                Some(value) => Navalias::new_complex(name, value),
             };
-           jspmeta.set_navalias(Some(navalias));
+            jspmeta.add_navalias(navalias.with_priority(priority));
+        }
+
+        for (name, value) in meta.env() {
+            jspmeta.add_env(name, value);
+        }
+
+        if let Some(envfile) = meta.envfile() {
+            jspmeta.set_envfile(Some(envfile.to_string()));
         }
     }
     jspmeta
@@ -3,12 +3,12 @@ use nom::{
     sequence::{tuple, preceded, delimited, separated_pair, terminated},
     bytes::complete::tag,
     branch::alt,
-    combinator::{map, recognize},
-    character::complete::space0,
+    combinator::{map, map_res, opt, recognize},
+    character::complete::{digit1, space0},
     multi::separated_nonempty_list,
 };
 
-use crate::jspt::{JsptMetadata, MetadataComponent, helpers::{variable, navalias_str, perm_chars} };
+use crate::jspt::{JsptMetadata, MetadataComponent, helpers::{variable, navalias_value, perm_chars} };
 
 /// Parses metadata from a a str, identifed from a list of identifiers surounded by
 /// square brackets. 
@@ -26,8 +26,12 @@ pub fn parse_metadata(input: &str) -> IResult<&str, JsptMetadata> {
                     MetadataComponent::Group(group) => metadata = metadata.set_group(Some(group)),
                     MetadataComponent::Volume => metadata = metadata.set_volume(true),
                     MetadataComponent::Autocreate => metadata = metadata.set_autocreate(true),
-                    MetadataComponent::NavAlias(name, None) => metadata = metadata.set_navalias( Some((name, None)) ),
-                    MetadataComponent::NavAlias(name, Some(value)) => metadata = metadata.set_navalias(Some((name,Some(value)))),
+                    MetadataComponent::Level => metadata = metadata.set_level(true),
+                    MetadataComponent::Entry(name) => metadata = metadata.set_entry(Some(name)),
+                    MetadataComponent::NavAlias(name, value) => metadata = metadata.add_navalias(name, value, 0),
+                    MetadataComponent::PriorityNavAlias(name, value, priority) => metadata = metadata.add_navalias(name, value, priority),
+                    MetadataComponent::EnvExport(name, value) => metadata = metadata.add_env(name, value),
+                    MetadataComponent::EnvFile(path) => metadata = metadata.set_envfile(Some(path)),
                     MetadataComponent::Separator => {
                         log::warn!("parse_metadata encountered Separateor");
                     }
@@ -46,6 +50,10 @@ pub fn parse_components(input: &str) -> IResult<&str, Vec<MetadataComponent>> {
             alt((
                 parse_navalias,
                 parse_autocreate,
+                parse_level,
+                parse_entry,
+                parse_envfile,
+                parse_env,
                 parse_volume,
                 parse_permissions,
                 parse_owner,
@@ -80,6 +88,18 @@ mod parse_components_tests {
        assert_eq!(owner, Ok(("", vec![MetadataComponent::NavAlias("cs".to_string(), Some("work.$USER".to_string()) )]))) ;
     }
 
+    #[test]
+    fn can_parse_level_and_varname() {
+       let owner = parse_components("[ level, varname: DD_EPISODE ]");
+       assert_eq!(owner, Ok(("", vec![MetadataComponent::Level, MetadataComponent::EnvVarName("DD_EPISODE".to_string())]))) ;
+    }
+
+    #[test]
+    fn can_parse_entry() {
+       let owner = parse_components("[ entry: assetdev, volume ]");
+       assert_eq!(owner, Ok(("", vec![MetadataComponent::Entry("assetdev".to_string()), MetadataComponent::Volume]))) ;
+    }
+
     #[test]
     fn can_parse_2_volumes() {
        let owner = parse_components("[ volume ,volume]");
@@ -229,6 +249,16 @@ fn parse_autocreate(input: &str) -> IResult<&str, MetadataComponent> {
 }
 
 
+fn parse_level(input: &str) -> IResult<&str, MetadataComponent> {
+    map(
+        delimited(space0, tag("level"), space0),
+        |_item| {
+            MetadataComponent::Level
+        }
+    )(input)
+}
+
+
 #[cfg(test)]
 mod autocreate_tests {
     use super::*;
@@ -430,6 +460,99 @@ fn parse_varname(input: &str) -> IResult<&str, MetadataComponent> {
 }
 
 
+fn parse_entry(input: &str) -> IResult<&str, MetadataComponent> {
+    map(
+        delimited(
+            space0,
+            separated_pair(
+                tag("entry"),
+                 preceded(space0,tag(":")), 
+                 preceded(space0, variable)
+            ), 
+            space0,
+        ),
+        |item| {
+            let (_, name) = item;
+            MetadataComponent::Entry(name.to_string())
+        }
+    )(input)
+}
+
+
+// env: DD_SHOT_ROOT=$path
+fn parse_env(input: &str) -> IResult<&str, MetadataComponent> {
+    map(
+        delimited(
+            space0,
+            tuple((
+                tag("env"),
+                preceded(space0, tag(":")), 
+                preceded(space0, variable),
+                preceded(space0, tag("=")), 
+                preceded(space0, navalias_value),
+            )), 
+            space0,
+        ),
+        |item| {
+            let (_, _, name, _, value) = item;
+            MetadataComponent::EnvExport(name.to_string(), value.to_string())
+        }
+    )(input)
+}
+
+// envfile: etc/show.env
+fn parse_envfile(input: &str) -> IResult<&str, MetadataComponent> {
+    map(
+        delimited(
+            space0,
+            separated_pair(
+                tag("envfile"),
+                 preceded(space0,tag(":")), 
+                 preceded(space0, navalias_value)
+            ), 
+            space0,
+        ),
+        |item| {
+            let (_, path) = item;
+            MetadataComponent::EnvFile(path.to_string())
+        }
+    )(input)
+}
+
+#[cfg(test)]
+mod env_tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_env() {
+       let env = parse_env("env: DD_SHOT_ROOT=$path");
+       assert_eq!(env, Ok(("", MetadataComponent::EnvExport("DD_SHOT_ROOT".to_string(), "$path".to_string())))) ;
+    }
+
+    #[test]
+    fn can_parse_multiple_env() {
+       let md = parse_metadata("[ varname: DD_SHOT, env: DD_SHOT_ROOT = $path, env: DD_LEVELSPEC=$levelspec ]").unwrap().1;
+       assert_eq!(
+           md.env().collect::<Vec<_>>(), 
+           vec![("DD_SHOT_ROOT", "$path"), ("DD_LEVELSPEC", "$levelspec")]
+       );
+    }
+
+    #[test]
+    fn can_parse_envfile() {
+       let envfile = parse_envfile("envfile: etc/show.env");
+       assert_eq!(envfile, Ok(("", MetadataComponent::EnvFile("etc/show.env".to_string())))) ;
+    }
+
+    #[test]
+    fn can_parse_envfile_with_env() {
+       let md = parse_metadata("[ varname: DD_SHOW, envfile: etc/show.env, env: DD_SHOW_ROOT=$path ]").unwrap().1;
+       assert_eq!(md.envfile(), Some("etc/show.env"));
+       assert_eq!(md.env().collect::<Vec<_>>(), vec![("DD_SHOW_ROOT", "$path")]);
+    }
+}
+
+
 #[cfg(test)]
 mod varname_tests {
     use super::*;
@@ -489,6 +612,51 @@ mod navalias_tests {
        assert_eq!(varname, Ok(("", MetadataComponent::NavAlias("cs".to_string(), Some("work.$USER".to_string()))))) ;
     }
 
+    #[test]
+    fn can_parse_navalias_with_path_value() {
+       let varname = parse_navalias("navalias: today logs/$DATE");
+       assert_eq!(varname, Ok(("", MetadataComponent::NavAlias("today".to_string(), Some("logs/$DATE".to_string()))))) ;
+    }
+
+    #[test]
+    fn can_parse_navalias_with_priority() {
+       let varname = parse_navalias("navalias(10): cs work.$USER");
+       assert_eq!(varname, Ok(("", MetadataComponent::PriorityNavAlias("cs".to_string(), Some("work.$USER".to_string()), 10)))) ;
+       let varname = parse_navalias("navalias (-1) : cs");
+       assert_eq!(varname, Ok(("", MetadataComponent::PriorityNavAlias("cs".to_string(), None, -1)))) ;
+    }
+
+    #[test]
+    fn can_parse_multiple_navaliases() {
+       let md = parse_metadata("[ navalias: cs work.$USER, navalias(5): today logs/$DATE ]").unwrap().1;
+       assert_eq!(
+           md.navaliases().collect::<Vec<_>>(), 
+           vec![("cs", Some("work.$USER"), 0), ("today", Some("logs/$DATE"), 5)]
+       );
+    }
+}
+
+// the optional priority following the navalias tag - `(10)`
+fn parse_navalias_priority(input: &str) -> IResult<&str, Option<i32>> {
+    opt(
+        delimited(
+            preceded(space0, tag("(")),
+            map_res(
+                preceded(space0, recognize(tuple((opt(tag("-")), digit1)))),
+                |priority: &str| priority.parse::<i32>()
+            ),
+            preceded(space0, tag(")")),
+        )
+    )(input)
+}
+
+// build the navalias component, which is only prioritized if a priority was supplied
+fn navalias_component(name: &str, value: Option<&str>, priority: Option<i32>) -> MetadataComponent {
+    let value = value.map(|v| v.to_string());
+    match priority {
+        Some(priority) => MetadataComponent::PriorityNavAlias(name.to_string(), value, priority),
+        None => MetadataComponent::NavAlias(name.to_string(), value),
+    }
 }
 
 // navalias: cs work.$JGERBER,
@@ -499,15 +667,16 @@ fn parse_navalias_some(input: &str) -> IResult<&str, MetadataComponent> {
             space0,
             tuple((
                 tag("navalias"),
+                parse_navalias_priority,
                 preceded(space0,tag(":")), 
                 preceded(space0, variable),
-                preceded(space0, navalias_str),
+                preceded(space0, navalias_value),
             )), 
             space0,
         ),
         |item| {
-            let (_, _, var_name, var_value) = item;
-            MetadataComponent::NavAlias(var_name.to_string(), Some(var_value.to_string()))
+            let (_, priority, _, var_name, var_value) = item;
+            navalias_component(var_name, Some(var_value), priority)
         }
     )(input)
 }
@@ -521,14 +690,15 @@ fn parse_navalias_none(input: &str) -> IResult<&str, MetadataComponent> {
 
             tuple((
                 tag("navalias"),
+                parse_navalias_priority,
                 preceded(space0,tag(":")), 
                 preceded(space0, variable),
             )), 
             space0,
         ),
         |item| {
-            let (_, _, var_name) = item;
-            MetadataComponent::NavAlias(var_name.to_string(), None)
+            let (_, priority, _, var_name) = item;
+            navalias_component(var_name, None, priority)
         }
     )(input)
 }
@@ -0,0 +1,271 @@
+//! The levels of the job hierarchy - by default show, sequence and shot - which
+//! make up a levelspec (eg `DEV01.RD.9999`). A template declares its own levels
+//! by tagging the level nodes with the `level` metadata key, in which case the
+//! levels are the level nodes along the path through the template which has
+//! the most of them, in order. Each level's varname names the environment
+//! variable used to expand relative levelspecs (eg `.RD.9999`).
+//!
+//! ```text
+//! show     = $show     [ level, varname: DD_SHOW ]
+//! episode  = $episode  [ level, varname: DD_EPISODE ]
+//! sequence = $sequence [ level, varname: DD_SEQUENCE ]
+//! shot     = $shot     [ level, varname: DD_SHOT ]
+//! ```
+use crate::{constants, JGraph, JSPError, NIndex};
+use petgraph::{visit::IntoNodeReferences, Direction::Outgoing};
+use std::{borrow::Cow, collections::HashMap, env};
+
+/// A single level of the job hierarchy
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Level {
+    /// The name of the template node
+    pub name: String,
+    /// The name of the environment variable holding the current value of the level
+    pub varname: Option<String>,
+}
+
+impl Level {
+    /// New up a Level
+    pub fn new<I: Into<String>>(name: I, varname: Option<&str>) -> Self {
+        Self {
+            name: name.into(),
+            varname: varname.map(|v| v.to_string()),
+        }
+    }
+}
+
+/// The ordered levels of the job hierarchy
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Levels {
+    levels: Vec<Level>,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        let varnames = [
+            constants::JSP_SHOW_ENVVAR,
+            constants::JSP_SEQUENCE_ENVVAR,
+            constants::JSP_SHOT_ENVVAR,
+        ];
+        Self::new(
+            constants::LEVELS.iter().zip(varnames.iter()).map(|(name, var)| Level::new(*name, Some(*var))).collect()
+        )
+    }
+}
+
+impl Levels {
+    /// New up Levels from a vector of Level, ordered from the top of the hierarchy
+    pub fn new(levels: Vec<Level>) -> Self {
+        Self { levels }
+    }
+
+    /// Retrieve the levels of the graph, as recorded on its root by
+    /// `graph::build_dispatch_index` when the graph was loaded. If they have
+    /// not been recorded, they are computed from the graph.
+    pub fn of(graph: &JGraph) -> Cow<Levels> {
+        match graph.node_references().next().and_then(|(_, node)| node.levels()) {
+            Some(levels) => Cow::Borrowed(levels),
+            None => Cow::Owned(Self::from_graph(graph)),
+        }
+    }
+
+    /// Compute the levels declared by the graph, walking the graph. If the graph
+    /// does not declare any, the default levels - show, sequence and shot - are
+    /// returned. Prefer `Levels::of`, which uses the levels recorded at load time.
+    pub fn from_graph(graph: &JGraph) -> Self {
+        let root = match graph.node_references().next() {
+            Some((idx, _)) => idx,
+            None => return Self::default(),
+        };
+        let mut memo = HashMap::new();
+        let chain = longest_chain(root, graph, &mut memo, &mut Vec::new());
+        if chain.is_empty() {
+            return Self::default();
+        }
+        Self::new(
+            chain.into_iter()
+                 .map(|idx| Level::new(graph[idx].name(), graph[idx].metadata().varname_ref()))
+                 .collect()
+        )
+    }
+
+    /// The number of levels
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Test to see if there are no levels
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Iterate over the levels, from the top of the hierarchy
+    pub fn iter(&self) -> std::slice::Iter<Level> {
+        self.levels.iter()
+    }
+
+    /// Retrieve the name of the level at `depth`
+    pub fn name(&self, depth: usize) -> Option<&str> {
+        self.levels.get(depth).map(|level| level.name.as_str())
+    }
+
+    /// Retrieve the name of the top level (eg `show`)
+    pub fn top(&self) -> Option<&str> {
+        self.name(0)
+    }
+
+    /// Test to see if the node named `name` is a level
+    pub fn contains(&self, name: &str) -> bool {
+        self.levels.iter().any(|level| level.name == name)
+    }
+
+    /// Parse a levelspec into its upper cased values, one per level. Empty
+    /// levels (eg `.RD.9999` or `DEV01..`) are relative, and are taken from the
+    /// environment variables named by each level's varname.
+    pub fn parse(&self, levelspec: &str) -> Result<Vec<String>, JSPError> {
+        let segments = levelspec.split('.').collect::<Vec<&str>>();
+        if levelspec.is_empty() || segments.len() > self.levels.len() {
+            return Err(JSPError::SearchTermError(
+                format!("'{}' is not a levelspec of at most {} levels", levelspec, self.levels.len())
+            ));
+        }
+        let mut values = Vec::with_capacity(segments.len());
+        for (depth, segment) in segments.iter().enumerate() {
+            if segment.is_empty() {
+                let level = &self.levels[depth];
+                let varname = level.varname.as_ref().ok_or_else(||
+                    JSPError::SearchTermError(format!("'{}' is relative, but the {} level has no varname", levelspec, level.name))
+                )?;
+                values.push(env::var(varname)?.to_uppercase());
+            } else if segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                values.push(segment.to_uppercase());
+            } else {
+                return Err(JSPError::SearchTermError(format!("invalid level '{}' in levelspec '{}'", segment, levelspec)));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Convert a levelspec into `key:value` search terms, one per level
+    pub fn to_terms(&self, levelspec: &str) -> Result<Vec<String>, JSPError> {
+        Ok(
+            self.parse(levelspec)?
+                .into_iter()
+                .zip(self.levels.iter())
+                .map(|(value, level)| format!("{}:{}", level.name, value))
+                .collect()
+        )
+    }
+
+    /// Build a levelspec from the values of the levels, as retrieved by `value`, stopping
+    /// at the first level without one. None if the top level has no value.
+    pub fn levelspec<'a, F>(&self, value: F) -> Option<String>
+    where
+        F: Fn(&str) -> Option<&'a str>
+    {
+        let values = self.levels.iter()
+                        .map_while(|level| value(&level.name))
+                        .collect::<Vec<&str>>();
+        if values.is_empty() { None } else { Some(values.join(".")) }
+    }
+}
+
+// find the path beneath idx, including idx, with the most level nodes, returning
+// the level nodes along it. `visiting` guards against cycles.
+fn longest_chain(idx: NIndex, graph: &JGraph, memo: &mut HashMap<NIndex, Vec<NIndex>>, visiting: &mut Vec<NIndex>) -> Vec<NIndex> {
+    if let Some(chain) = memo.get(&idx) {
+        return chain.clone();
+    }
+    if visiting.contains(&idx) {
+        return Vec::new();
+    }
+    visiting.push(idx);
+    let mut best = Vec::new();
+    let mut children = graph.neighbors_directed(idx, Outgoing).collect::<Vec<NIndex>>();
+    // petgraph iterates neighbors most recently added first
+    children.reverse();
+    for child in children {
+        let chain = longest_chain(child, graph, memo, visiting);
+        if chain.len() > best.len() {
+            best = chain;
+        }
+    }
+    visiting.pop();
+    if graph[idx].metadata().level() {
+        best.insert(0, idx);
+    }
+    memo.insert(idx, best.clone());
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::testdata::build_graph, jspnode, EntryType, Node, NodeType, Regexp};
+
+    fn episodic_graph() -> JGraph {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let shows = graph.add_node(jspnode!("shows"));
+        let show = graph.add_node(jspnode!("show", r"^[A-Z]+[A-Z0-9]*$", "varname" => "DD_SHOW", "level" => "true"));
+        let episode = graph.add_node(jspnode!("episode", r"^EP[0-9]+$", "varname" => "DD_EPISODE", "level" => "true"));
+        let sequence = graph.add_node(jspnode!("sequence", r"^[A-Z]{2,4}$", "varname" => "DD_SEQUENCE", "level" => "true"));
+        let shot = graph.add_node(jspnode!("shot", r"^[0-9]+$", "varname" => "DD_SHOT", "level" => "true"));
+        let etc = graph.add_node(jspnode!("etc"));
+        graph.extend_with_edges(&[
+            (root, dd), (dd, shows), (shows, show), (show, etc),
+            (show, episode), (episode, sequence), (sequence, shot),
+        ]);
+        graph
+    }
+
+    #[test]
+    fn graph_without_levels_uses_default() {
+        let graph = build_graph();
+        let levels = Levels::from_graph(&graph);
+        assert_eq!(levels, Levels::default());
+        assert_eq!(levels.top(), Some("show"));
+        assert_eq!(levels.to_terms("dev01.rd.9999").unwrap(), vec!["show:DEV01", "sequence:RD", "shot:9999"]);
+    }
+
+    #[test]
+    fn can_declare_levels_in_graph() {
+        let graph = episodic_graph();
+        let levels = Levels::from_graph(&graph);
+        assert_eq!(levels.len(), 4);
+        assert_eq!(levels.name(1), Some("episode"));
+        assert_eq!(levels.iter().nth(1).unwrap().varname, Some("DD_EPISODE".to_string()));
+        assert_eq!(
+            levels.to_terms("dev01.ep01.rd.0001").unwrap(),
+            vec!["show:DEV01", "episode:EP01", "sequence:RD", "shot:0001"]
+        );
+        assert!(levels.parse("dev01.ep01.rd.0001.foo").is_err());
+    }
+
+    #[test]
+    fn levels_are_recorded_by_dispatch_index() {
+        let mut graph = episodic_graph();
+        assert!(matches!(Levels::of(&graph), Cow::Owned(_)));
+        crate::build_dispatch_index(&mut graph);
+        let levels = Levels::of(&graph);
+        assert!(matches!(levels, Cow::Borrowed(_)));
+        assert_eq!(*levels, Levels::from_graph(&graph));
+    }
+
+    #[test]
+    fn can_build_levelspec() {
+        let levels = Levels::default();
+        let values = vec![("show", "DEV01"), ("sequence", "RD")].into_iter().collect::<HashMap<&str, &str>>();
+        assert_eq!(levels.levelspec(|name| values.get(name).cloned()), Some("DEV01.RD".to_string()));
+        assert_eq!(levels.levelspec(|_| None), None);
+    }
+
+    #[test]
+    fn invalid_levels_are_err() {
+        let levels = Levels::default();
+        assert!(levels.parse("").is_err());
+        assert!(levels.parse("DEV01.RD.9999.A").is_err());
+        assert!(levels.parse("DEV-01").is_err());
+    }
+}
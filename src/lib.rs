@@ -22,6 +22,9 @@ pub use nodetype::{NodeType, ValidType};
 pub mod entrytype;
 pub use entrytype::EntryType;
 
+pub mod dispatch;
+pub use dispatch::Dispatch;
+
 pub mod node;
 pub use node::{Node };
 
@@ -29,7 +32,7 @@ pub mod nodepath;
 pub use nodepath::NodePath;
 
 pub mod graph;
-pub use graph::{validate_path, JGraph, get_graph, get_graph_from_fn};
+pub use graph::{validate_path, validate_path_from, validate_path_from_name, build_dispatch_index, JGraph, get_graph, get_graph_from_fn, get_show_etc_dir, get_show_etc_dir_from, get_show_template_for_path, get_template_from_env};
 
 pub mod returnvalue;
 pub use returnvalue::{ ReturnValue, NIndex };
@@ -46,7 +49,7 @@ pub use user::{User, get_default_user};
 pub mod constants;
 
 pub mod find;
-pub use find::{find, find_path, find_path_from_terms, find_rel, FindRelStrategy};
+pub use find::{find, find_from, find_path, find_path_from, find_path_from_terms, find_rel, FindRelStrategy};
 
 pub mod searchterm;
 pub use searchterm::{Search, SearchTerm};
@@ -61,7 +64,12 @@ pub mod cachedaliases;
 pub use cachedaliases::CachedAliases;
 
 pub mod shell;
-pub use shell::{bash, tcsh, ShellEnvManager, SupportedShell};
+pub use shell::{bash, fish, tcsh, zsh, ShellEnvManager, SupportedShell};
+
+pub mod goplan;
+pub use goplan::{EnvFormat, GoFormat, GoOutcome, GoPlan};
+
+pub mod run;
 
 pub mod cli;
 pub use cli::{gen_terms_from_strings};
@@ -76,6 +84,48 @@ pub use validpath::ValidPath;
 pub mod minimatch;
 pub use minimatch::parse_show_from_arg;
 
+pub mod pathcontext;
+pub use pathcontext::PathContext;
+
+pub mod levels;
+pub use levels::{Level, Levels};
+
+pub mod address;
+pub use address::Address;
+
+pub mod envfile;
+pub use envfile::EnvFile;
+
+pub mod fuzzy;
+
+pub mod bookmark;
+pub use bookmark::{Bookmark, Bookmarks};
+
+pub mod history;
+pub use history::{History, HistoryEntry};
+
+pub mod graphcache;
+
+pub mod prompt;
+
+pub mod location;
+pub use location::Location;
+
+pub mod pathformat;
+pub use pathformat::{format_path, format_paths};
+
+pub mod crawler;
+pub use crawler::{crawl, CrawlEntry};
+
+pub mod complete;
+pub use complete::{Completion, CompletionKind};
+
+pub mod bulk;
+pub use bulk::{BulkResult, BulkFailure};
+
+#[cfg(test)]
+pub(crate) mod testutil;
+
 
 #[macro_export]
 macro_rules!  jspnode {
@@ -116,13 +166,22 @@ macro_rules!  jspnode {
                 }
                 "varname" => {n.metadata_mut().set_varname(Some(String::from($val)));}
                 "autocreate" => {n.metadata_mut().set_autocreate($val.parse().unwrap_or_else(|_v| false));}
+                "level" => {n.metadata_mut().set_level($val.parse().unwrap_or_else(|_v| false));}
+                "entry" => {n.metadata_mut().set_entry(Some(String::from($val)));}
+                "envfile" => {n.metadata_mut().set_envfile(Some(String::from($val)));}
+                "env" => {
+                    if let Some(idx) = $val.find("=") {
+                        let (key, value) = $val.split_at(idx);
+                        n.metadata_mut().add_env(key, value.trim_start_matches('='));
+                    }
+                }
                 "navalias" => {
                     if let Some(idx) = $val.find("=") {
                         let (key, value) = $val.split_at(idx);
                         let value = value.trim_start_matches('=');
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Complex{name:key.to_owned(), value: value.to_owned()} ));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_complex(key, value));
                     } else {
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Simple($val.to_owned())));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_simple($val));
                     }
                 }
                 _ => ()
@@ -172,13 +231,22 @@ macro_rules!  jspnode {
                 }
                 "varname" => {n.metadata_mut().set_varname(Some(String::from($val)));}
                 "autocreate" => {n.metadata_mut().set_autocreate($val.parse().unwrap_or_else(|_v| false));}
+                "level" => {n.metadata_mut().set_level($val.parse().unwrap_or_else(|_v| false));}
+                "entry" => {n.metadata_mut().set_entry(Some(String::from($val)));}
+                "envfile" => {n.metadata_mut().set_envfile(Some(String::from($val)));}
+                "env" => {
+                    if let Some(idx) = $val.find("=") {
+                        let (key, value) = $val.split_at(idx);
+                        n.metadata_mut().add_env(key, value.trim_start_matches('='));
+                    }
+                }
                 "navalias" => {
                     if let Some(idx) = $val.find("=") {
                         let (key, value) = $val.split_at(idx);
                         let value = value.trim_start_matches('=');
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Complex{name:key.to_owned(), value: value.to_owned()} ));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_complex(key, value));
                     } else {
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Simple($val.to_owned())));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_simple($val));
                     }
                 }
                 _ => ()
@@ -229,13 +297,22 @@ macro_rules!  jspnode {
                 }
                 "varname" => {n.metadata_mut().set_varname(Some(String::from($val)));}
                 "autocreate" => {n.metadata_mut().set_autocreate($val.parse().unwrap_or_else(|_v| false));}
+                "level" => {n.metadata_mut().set_level($val.parse().unwrap_or_else(|_v| false));}
+                "entry" => {n.metadata_mut().set_entry(Some(String::from($val)));}
+                "envfile" => {n.metadata_mut().set_envfile(Some(String::from($val)));}
+                "env" => {
+                    if let Some(idx) = $val.find("=") {
+                        let (key, value) = $val.split_at(idx);
+                        n.metadata_mut().add_env(key, value.trim_start_matches('='));
+                    }
+                }
                 "navalias" => {
                     if let Some(idx) = $val.find("=") {
                         let (key, value) = $val.split_at(idx);
                         let value = value.trim_start_matches('=');
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Complex{name:key.to_owned(), value: value.to_owned()} ));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_complex(key, value));
                     } else {
-                        n.metadata_mut().set_navalias(Some(crate::navalias::Navalias::Simple($val.to_owned())));
+                        n.metadata_mut().add_navalias(crate::navalias::Navalias::new_simple($val));
                     }
                 }
                 _ => ()
@@ -0,0 +1,147 @@
+//! Location works backwards from an arbitrary path - typically the current 
+//! working directory - to the nearest valid location in the template. It does
+//! not consult the `DD_*` environment variables, which are stale as soon as 
+//! the user changes directory without `jspgo`. Nor does it consult the record
+//! of variables and aliases previously set by `jspgo`, or read env files, as it
+//! is used to draw the prompt.
+use crate::{cli, diskutils, validate_path, JGraph, JSPError, NodeType, PathContext, ValidPath};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+};
+
+/// The nearest valid template location to a path.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Location {
+    /// The path the location was resolved from
+    pub path: PathBuf,
+    /// The levelspec of the location (eg `DEV01.RD.9999`), if it is at or below a show
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub levelspec: Option<String>,
+    /// The context of the location - its path, varnames, captures, and owner
+    pub context: PathContext,
+    /// The navaliases available from the location, as `jsp go` would set them
+    pub navaliases: BTreeMap<String, PathBuf>,
+    /// The number of components of `path` which extend beyond the location, into
+    /// territory which the template does not track
+    pub untracked: usize,
+}
+
+impl Location {
+    /// New up a Location by finding the nearest valid template location to `path`.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path to resolve. Relative paths are converted to absolute paths.
+    /// * `graph` - Reference to the JGraph
+    ///
+    /// # Returns
+    /// Ok wrapping a Location if any ancestor of the path is valid. Otherwise, an 
+    /// Err wrapping the JSPError encountered validating the path.
+    pub fn new<I: Into<PathBuf>>(path: I, graph: &JGraph) -> Result<Self, JSPError> {
+        let path = diskutils::convert_relative_pathbuf_to_absolute(path.into())?;
+        let components = path.components().count();
+
+        let mut candidate = path.clone();
+        let mut error = None;
+        let (location, nodepath) = loop {
+            match validate_path(&candidate, graph) {
+                Ok(nodepath) => break (candidate, nodepath),
+                Err(e) => {
+                    let e = error.get_or_insert(e).clone();
+                    if !candidate.pop() {
+                        return Err(e);
+                    }
+                }
+            }
+        };
+        // the location ends at the first untracked node. The root alone is not a location.
+        let tracked = nodepath.iter().take_while(|n| !matches!(n.identity(), NodeType::Untracked)).count();
+        if tracked < 2 {
+            return Err(error.unwrap_or_else(|| JSPError::FindFailure(format!("no location for {:?}", path))));
+        }
+        let location = location.components().take(tracked).collect::<PathBuf>();
+        let validpath = ValidPath::new(location, graph, false)?;
+        let context = PathContext::from_validpath(&validpath);
+
+        Ok(Self {
+            levelspec: context.levelspec(),
+            path,
+            context,
+            navaliases: cli::navaliases(&validpath, graph),
+            untracked: components - tracked,
+        })
+    }
+
+    /// The nearest valid location
+    pub fn location(&self) -> &PathBuf {
+        &self.context.path
+    }
+
+    /// Retrieve the value of the template variable `varname` at the location
+    pub fn var(&self, varname: &str) -> Option<&str> {
+        self.context.var(varname)
+    }
+
+    /// Is the path itself a location tracked by the template?
+    pub fn is_tracked(&self) -> bool {
+        self.untracked == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::testdata::build_graph, jspnode, EntryType, Node, Regexp};
+    use std::path::Path;
+
+    #[test]
+    fn can_locate_tracked_path() {
+        let graph = build_graph();
+        let location = Location::new("/dd/shows/DEV01/RD/9999/user/work.jgerber", &graph).unwrap();
+        assert_eq!(location.levelspec, Some("DEV01.RD.9999".to_string()));
+        assert_eq!(location.var("DD_SHOW"), Some("DEV01"));
+        assert!(location.is_tracked());
+    }
+
+    #[test]
+    fn can_locate_untracked_path() {
+        let graph = build_graph();
+        let location = Location::new("/dd/shows/DEV01/RD/9999/FOO/bar", &graph).unwrap();
+        assert_eq!(location.location(), Path::new("/dd/shows/DEV01/RD/9999"));
+        assert_eq!(location.levelspec, Some("DEV01.RD.9999".to_string()));
+        assert_eq!(location.untracked, 2);
+        assert_eq!(location.path, PathBuf::from("/dd/shows/DEV01/RD/9999/FOO/bar"));
+    }
+
+    #[test]
+    fn can_locate_navaliases() {
+        let mut graph = JGraph::new();
+        let root = graph.add_node(Node::new_root());
+        let dd = graph.add_node(jspnode!("dd"));
+        let proj = graph.add_node(jspnode!("proj", r"^[a-z]+$", "navalias" => "cdproj"));
+        let tools = graph.add_node(jspnode!("tools", "navalias" => "cdtools"));
+        let logs = graph.add_node(jspnode!("logs", "navalias" => "today=logs/$JSP_NO_SUCH_VAR_SET"));
+        graph.extend_with_edges(&[(root, dd), (dd, proj), (proj, tools), (proj, logs)]);
+
+        let location = Location::new("/dd/myproj/untracked", &graph).unwrap();
+        assert_eq!(location.navaliases.get("cdproj"), Some(&PathBuf::from("/dd/myproj")));
+        assert_eq!(location.navaliases.get("cdtools"), Some(&PathBuf::from("/dd/myproj/tools")));
+        // aliases which cannot be resolved are skipped
+        assert_eq!(location.navaliases.get("today"), None);
+    }
+
+    #[test]
+    fn path_outside_template_is_err() {
+        let graph = build_graph();
+        assert!(Location::new("/tmp/foo", &graph).is_err());
+    }
+
+    #[test]
+    fn levelspec_requires_show() {
+        let graph = build_graph();
+        let location = Location::new("/dd/shows", &graph).unwrap();
+        assert_eq!(location.levelspec, None);
+    }
+}
@@ -1,4 +1,4 @@
-use chrono;
+use chrono::{self, TimeZone};
 use dotenv::dotenv;
 use fern::{ 
     colors::{
@@ -8,23 +8,42 @@ use fern::{
     self
 };
 use jsp::{ 
+    bookmark,
+    bulk,
+    complete,
+    constants,
+    graphcache,
+    prompt,
     report,
-    cli,
+    run,
+    cli::{self, MissingTarget},
     diskutils, 
     validate_path, 
     JSPError, 
     get_graph,
     get_graph_from_fn,
+    get_show_etc_dir,
+    get_show_etc_dir_from,
+    get_show_template_for_path,
+    get_template_from_env,
     parse_show_from_arg,
     gen_terms_from_strings,
     find,
+    Bookmark,
+    Bookmarks,
+    EnvFormat,
+    GoFormat,
+    History,
+    history,
     JGraph, 
+    Levels,
+    Location,
+    SupportedShell,
     jspt::{JGraphKeyMap, RegexMap},
 };
-use levelspecter::{LevelSpec, LevelType};
 use log::{ LevelFilter, self };
 use petgraph;
-use std::path::PathBuf ;
+use std::{io, os::unix::process::CommandExt, path::{Path, PathBuf}};
 use structopt::StructOpt;
 
 
@@ -64,17 +83,43 @@ struct Opt {
 
 }
 
+#[derive(StructOpt, Debug)]
+enum BookmarkCmd {
+    /// Bookmark the location described by a levelspec and key:value terms, or
+    /// the current directory if no terms are supplied
+    #[structopt(name = "add")]
+    Add {
+        /// The bookmark name, made up of alphanumerics, _, - and .
+        #[structopt(name="NAME")]
+        name: String,
+
+        /// a levelspec, followed by zero or more key:value terms
+        #[structopt(name="TERMS")]
+        terms: Vec<String>,
+
+        /// add the bookmark to the show's shared bookmarks, rather than your own
+        #[structopt(long = "shared")]
+        shared: bool,
+    },
+    /// List your bookmarks, and the shared bookmarks of the current show
+    #[structopt(name = "ls")]
+    Ls {},
+}
+
 #[derive(StructOpt, Debug)]
 enum Subcommand {
     /// Navigation command
-    #[structopt(name = "go")]
+    #[structopt(name = "go", raw(setting = "structopt::clap::AppSettings::AllowNegativeNumbers"))]
     Go {
         /// one or more search tearms of the form key:value , or a 
-        /// fullpath, depending upon other field
+        /// fullpath, depending upon other field. `levelspec:entry/path` addresses
+        /// a branch beneath a levelspec (eg dev01:assetdev/BOBCHAR). `-` returns 
+        /// to the previous location, `-N` to the Nth previous location, and 
+        /// `@name` to a bookmark.
         #[structopt(name="TERMS")]
         terms: Vec<String>,
 
-        /// choose a shell (bash)
+        /// choose a shell (bash, tcsh, zsh, or fish)
         #[structopt(short = "s", long = "shell")]
         myshell: Option<String>,
 
@@ -82,11 +127,161 @@ enum Subcommand {
         #[structopt(short = "f", long = "fullpath")]
         full_path: bool,
 
+        /// choose an output format: shell code to eval (shell), or a description
+        /// of the changes to the environment (json)
+        #[structopt(long = "format")]
+        format: Option<String>,
+
+        /// match partial or abbreviated levels (eg dev.r.99) against the existing
+        /// locations
+        #[structopt(long = "fuzzy")]
+        fuzzy: bool,
+
+        /// create the location, as jspmk would, if it does not exist. By default,
+        /// jspgo navigates to the deepest existing ancestor of the location.
+        #[structopt(long = "create", conflicts_with = "strict")]
+        create: bool,
+
+        /// fail if the location does not exist, rather than navigating to its
+        /// deepest existing ancestor
+        #[structopt(long = "strict")]
+        strict: bool,
+
         /// Print Success / Failure information. And in color!
         #[structopt(short = "v", long = "verbose")]
         verbose: bool,
 
-    }
+    },
+    /// Print the environment variables and navaliases for a location, without
+    /// changing directory
+    #[structopt(name = "env")]
+    Env {
+        /// one or more search tearms of the form key:value , or a 
+        /// fullpath, depending upon other field
+        #[structopt(name="TERMS")]
+        terms: Vec<String>,
+
+        /// choose an output format: bash, tcsh, zsh, fish, dotenv, or json
+        #[structopt(long = "format")]
+        format: Option<String>,
+
+        /// accept a fullpath instead of key:value pairs
+        #[structopt(short = "f", long = "fullpath")]
+        full_path: bool,
+
+        /// match partial or abbreviated levels (eg dev.r.99) against the existing
+        /// locations
+        #[structopt(long = "fuzzy")]
+        fuzzy: bool,
+
+        /// Print Success / Failure information. And in color!
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+    },
+    /// Run a command in a location, with the location's environment variables set,
+    /// and its navaliases exported as JSP_NAV_<NAME>. eg `jsp run dev01.rd.9999 -- maya`
+    #[structopt(name = "run")]
+    Run {
+        /// one or more search tearms of the form key:value , or a 
+        /// fullpath, depending upon other field
+        #[structopt(name="TERMS")]
+        terms: Vec<String>,
+
+        /// The command to run, and its arguments, following `--`
+        #[structopt(name="COMMAND", raw(last = "true"))]
+        command: Vec<String>,
+
+        /// create the location first, using jspmk, if it does not exist
+        #[structopt(short = "c", long = "create")]
+        create: bool,
+
+        /// accept a fullpath instead of key:value pairs
+        #[structopt(short = "f", long = "fullpath")]
+        full_path: bool,
+
+        /// match partial or abbreviated levels (eg dev.r.99) against the existing
+        /// locations
+        #[structopt(long = "fuzzy")]
+        fuzzy: bool,
+
+        /// Print Success / Failure information. And in color!
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+    },
+    /// Work out the nearest valid location to a path (by default, the current
+    /// directory), without consulting the DD_* environment variables
+    #[structopt(name = "context")]
+    Context {
+        /// The path to resolve. Defaults to the current directory
+        #[structopt(name="PATH", parse(from_os_str))]
+        path: Option<PathBuf>,
+
+        /// Print the context as json
+        #[structopt(short = "j", long = "json")]
+        json: bool,
+    },
+    /// Print a compact description of the location of the current directory, for
+    /// use in a shell prompt. Never fails; prints nothing outside of a location.
+    #[structopt(name = "prompt")]
+    Prompt {
+        /// The format. Defaults to JSP_PROMPT_FORMAT, or "{levels} [{leaf}]".
+        /// Tokens: {levels} {levelspec} {leaf} {owner} {untracked} {location}, 
+        /// and node, capture, or variable names, eg {show}
+        #[structopt(long = "format")]
+        format: Option<String>,
+
+        /// The path to describe. Defaults to the current directory
+        #[structopt(name="PATH", parse(from_os_str))]
+        path: Option<PathBuf>,
+    },
+    /// Manage bookmarks, which are navigated to via `jspgo @name`
+    #[structopt(name = "bookmark")]
+    Bookmark {
+        #[structopt(subcommand)]
+        cmd: BookmarkCmd,
+    },
+    /// List the locations previously navigated to, most recent last. The
+    /// number beside each is usable as `jspgo -N`
+    #[structopt(name = "history")]
+    History {
+        /// The maximum number of entries to list
+        #[structopt(short = "n", long = "count")]
+        count: Option<usize>,
+    },
+    /// Cache the template, so that jsp prompt does not have to parse it, along with
+    /// the template of the show containing PATH, if it has one of its own
+    #[structopt(name = "cache")]
+    Cache {
+        /// A path within the show. Defaults to the current directory
+        #[structopt(name="PATH", parse(from_os_str))]
+        path: Option<PathBuf>,
+    },
+    /// Validate paths or levelspecs read from stdin, one per line, writing
+    /// the result for each as a line of json to stdout. The template is
+    /// read from --input, or from the JSP_PATH environment variable.
+    #[structopt(name = "bulk")]
+    Bulk {},
+    /// Complete the last of the supplied words, printing one candidate per
+    /// line. Intended to be called by shell completion scripts.
+    #[structopt(name = "complete", raw(setting = "structopt::clap::AppSettings::Hidden"))]
+    Complete {
+        /// The command line, up to the cursor, as supplied by the shell. This
+        /// takes the place of WORDS.
+        #[structopt(long = "line")]
+        line: Option<String>,
+
+        /// The words on the command line. The last word is completed.
+        #[structopt(name="WORDS")]
+        words: Vec<String>,
+    },
+    /// Print a script which registers completion of jspgo and jspmk
+    /// for the supplied shell (bash, tcsh, zsh, or fish)
+    #[structopt(name = "completion")]
+    Completion {
+        /// The shell to generate the completion script for
+        #[structopt(name="SHELL")]
+        myshell: String,
+    },
 }
 
 fn main() {
@@ -98,18 +293,32 @@ fn main() {
     setup_logger(level).unwrap();
     
     let Opt{verbose, dot, graph, terms, subcmd, ..} = args;
+    // diagnostics are written to stderr, and the failure is reported via the exit 
+    // code, so that stdout only ever holds the requested output
     match doit(dot, graph, terms, subcmd) {
         Ok(_) => (),
         Err(JSPError::EmptyArgumentListError) => {
             report::shellerror("Error: No arguments supplied to command", None, verbose);
+            std::process::exit(JSPError::EmptyArgumentListError.exit_code());
+        },
+        Err(JSPError::AmbiguousLevelspec{input, candidates}) => {
+            report::shellerror(&format!("'{}' matches more than one location. Candidates, best first:", input), None, verbose);
+            for candidate in &candidates {
+                eprintln!("    {}", candidate);
+            }
+            std::process::exit(constants::EXIT_INVALID);
         },
-        Err(JSPError::IoError(e)) => {
+        Err(e @ JSPError::IoError{kind: io::ErrorKind::NotFound, ..}) => {
+            let code = e.exit_code();
             report::shellerror("The supplied input does not resolve to a valid directory or file",
-            Some(JSPError::IoError(e)), 
+            Some(e), 
             verbose);
+            std::process::exit(code);
         },
         Err(e) => {
+            let code = e.exit_code();
             report::shellerror("Error Encountered", Some(e), verbose);
+            std::process::exit(code);
         }
     }
 }
@@ -118,134 +327,374 @@ fn doit(dot: Option<PathBuf>, graph: Option<PathBuf>, terms: Vec<String>, subcmd
 -> Result<(), JSPError> {
 
 
-    if let Some(Subcommand::Go{terms, myshell, full_path, verbose}) = subcmd {
-        if terms.is_empty() { return Err(JSPError::EmptyArgumentListError);}
-
-        let (graph,  _keymap,  _regexmap) =  {
-            get_graph_from_fn(graph, &terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(), |_|{ 
-                // get the graph
-                let (graph, keymap, _regexmap) = get_graph(None)?;
-                let term = match LevelSpec::new(&terms[0]) {
-                    Ok(ls) => {
-                        let show = ls.show();
-                        if show == &LevelType::Relative { std::env::var("DD_SHOW")? } else { show.to_str().to_owned() } 
-                    },
-                    // we assume that a path was passed in as opposed to a levelspec
-                    Err(_) => terms[0].to_string(),
-
-                };
-                let search = vec![term];
-                // todo handle abs path
-                let mut validpath = cli::validpath_from_terms(search, &graph, false, full_path)?;
-                let idx = keymap.get("show").unwrap();
-                log::trace!("got index {:?}",idx );
-                validpath.remove_past(idx)?;
-                
-                let mut pathbuf = validpath.pathbuf();
-                pathbuf.push("etc");
-                pathbuf.push("template.jspt");
-                log::info!("Returning template {:?}", pathbuf);
-                Ok( pathbuf)
-            })?
-        };
-        /*
-        let (graph,  _keymap,  _regexmap) =  get_graph_main(
-            terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(),
-            graph
-        )?;
-        */
-        match cli::go(terms, myshell, &graph, full_path, verbose) {
-            Ok(_validpath) => (),
-            Err(e) => {
-                return Err(e);
-            }
-        }
-    //
-    // Validate supplied argument to determine whether it is a valid path or not
-    //
-    } else {
-
-        if dot.is_some() {
+    match subcmd {
+        Some(Subcommand::Go{terms, myshell, full_path, format, fuzzy, create, strict, verbose}) => {
+            if terms.is_empty() { return Err(JSPError::EmptyArgumentListError);}
+            let format = format.map(|f| f.parse::<GoFormat>()).transpose()?.unwrap_or_default();
+            let terms = resolve_bookmark(terms)?;
+            let (terms, full_path) = resolve_back_reference(terms, full_path)?;
+            let (graph,  _keymap,  _regexmap) = get_graph_for_terms(graph, &terms, full_path, fuzzy)?;
+            /*
             let (graph,  _keymap,  _regexmap) =  get_graph_main(
                 terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(),
                 graph
             )?;
-            
-            if let Some(mut output) = dot {
-                if !terms.is_empty(){
-                    log::warn!("INPUT not compatible with --dot argument. It will be ignored");
+            */
+            let missing = if create {
+                MissingTarget::Create{ jspmk: cli::installed_jspmk()?, ignore_volume: false }
+            } else if strict {
+                MissingTarget::Fail
+            } else {
+                MissingTarget::Ancestor
+            };
+            match format {
+                GoFormat::Shell => {
+                    match cli::go(terms, myshell, &graph, full_path, fuzzy, &missing, verbose) {
+                        Ok(_validpath) => (),
+                        Err(e) => {
+                            return Err(e);
+                        }
+                    }
+                }
+                GoFormat::Json => {
+                    let (validpath, plan) = cli::go_plan(terms, &graph, full_path, fuzzy, verbose)?;
+                    let (_validpath, plan) = cli::resolve_missing_target(validpath, plan, &graph, &missing, verbose)?;
+                    println!("{}", plan.to_json()?);
                 }
-                output = diskutils::convert_relative_pathbuf_to_absolute(output)?;
-                // TODO: check to see that output doesnt exist and that its parent partory does exist
-                diskutils::write_template_as_dotfile(&output, &graph);
+            }
+        },
+        //
+        // Print the environment for a location
+        //
+        Some(Subcommand::Env{terms, format, full_path, fuzzy, verbose}) => {
+            if terms.is_empty() { return Err(JSPError::EmptyArgumentListError);}
+            let format = format.map(|f| f.parse::<EnvFormat>()).transpose()?.unwrap_or_default();
+            let (graph,  _keymap,  _regexmap) = get_graph_for_terms(graph, &terms, full_path, fuzzy)?;
+            let (_validpath, plan) = cli::go_plan(terms, &graph, full_path, fuzzy, verbose)?;
+            print!("{}", plan.render_as(&format)?);
+        },
+        //
+        // Replace this process with the command, run in the location
+        //
+        Some(Subcommand::Run{terms, command, create, full_path, fuzzy, verbose}) => {
+            if terms.is_empty() || command.is_empty() { return Err(JSPError::EmptyArgumentListError);}
+            let (graph,  _keymap,  _regexmap) = get_graph_for_terms(graph, &terms, full_path, fuzzy)?;
+            let (_validpath, plan) = cli::go_plan(terms, &graph, full_path, fuzzy, verbose)?;
+            if create && !plan.target.exists() {
+                // jspmk is responsible for creating directories with the correct ownership
+                cli::mk_with_jspmk(&cli::installed_jspmk()?, &plan.target, false)?;
+            }
+            // the command must not inherit the privileges jsp is installed with
+            run::drop_privileges()?;
+            let error = run::command(&plan, &command)?.exec();
+            return Err(JSPError::IoError{ kind: error.kind(), msg: format!("unable to run {:?}: {}", command, error) });
+        },
+        //
+        // Report the nearest valid location to a path
+        //
+        Some(Subcommand::Context{path, json}) => {
+            let path = match path {
+                Some(path) => diskutils::convert_relative_pathbuf_to_absolute(path)?,
+                None => std::env::current_dir()?,
+            };
+            let graph = get_graph_for_path(graph, &path)?;
+            let location = Location::new(path, &graph)?;
+            if json {
+                println!("{}", serde_json::to_string(&location).map_err(|e| JSPError::GeneralError(e.to_string()))?);
             } else {
-                println!("{:#?}",  petgraph::dot::Dot::with_config(&graph, &[petgraph::dot::Config::EdgeNoLabel]));
+                println!("location: {}", location.location().display());
+                if let Some(ref levelspec) = location.levelspec {
+                    println!("levelspec: {}", levelspec);
+                }
+                println!("untracked: {}", location.untracked);
+                for (varname, value) in &location.context.vars {
+                    println!("{}={}", varname, value);
+                }
+                for (name, value) in &location.navaliases {
+                    println!("navalias {}={}", name, value.display());
+                }
             }
-        } else if !terms.is_empty() {
-
-            let (graph,  _keymap,  _regexmap) =  {
-                get_graph_from_fn(graph, &terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(), |_|{ 
-                    // get the graph
-                    let (graph, keymap, _regexmap) = get_graph(None)?;
-                    let term = match LevelSpec::new(&terms[0]) {
-                        Ok(ls) => {
-                            let show = ls.show();
-                            if show == &LevelType::Relative { std::env::var("DD_SHOW")? } else { show.to_str().to_owned() } 
-                        },
-                        // we assume that a path was passed in as opposed to a levelspec
-                        Err(_) => terms[0].to_string(),
+        },
+        //
+        // Print the prompt segment. Any failure results in an empty prompt
+        //
+        Some(Subcommand::Prompt{format, path}) => {
+            let format = format.or_else(|| std::env::var(constants::JSP_PROMPT_FORMAT).ok())
+                               .unwrap_or_else(|| prompt::DEFAULT_PROMPT_FORMAT.to_string());
+            let path = match path {
+                Some(path) => diskutils::convert_relative_pathbuf_to_absolute(path).ok(),
+                None => std::env::current_dir().ok(),
+            };
+            let graph = path.as_ref().and_then(|path| get_graph_for_path(graph, path).ok());
+            if let (Some(graph), Some(path)) = (graph, path) {
+                if let Some(segment) = prompt::prompt(&format, &path, &graph) {
+                    println!("{}", segment);
+                }
+            }
+        },
+        //
+        // Add a bookmark
+        //
+        Some(Subcommand::Bookmark{cmd: BookmarkCmd::Add{name, terms, shared}}) => {
+            let bookmark = if terms.is_empty() {
+                let path = std::env::current_dir()?;
+                let graph = get_graph_for_path(graph, &path)?;
+                Bookmark::from_path(path, &graph)?
+            } else {
+                let (graph,  _keymap,  _regexmap) = get_graph_for_terms(graph, &terms, false, false)?;
+                let bookmark = Bookmark::from_terms(&terms, &graph)?;
+                // make sure that the bookmark resolves before storing it
+                cli::validpath_from_terms(bookmark.to_terms(), &graph, false, false, false)?;
+                bookmark
+            };
+            let mut bookmarks = if shared {
+                Bookmarks::from_file(Bookmarks::shared_file(&get_show_etc_dir(bookmark.show(), false, false)?))?
+            } else {
+                Bookmarks::load_user()?
+            };
+            let description = bookmark.to_terms().join(" ");
+            bookmarks.insert(&name, bookmark)?;
+            bookmarks.save()?;
+            eprintln!("Bookmarked @{} as {} in {}", name, description, bookmarks.file().display());
+        },
+        //
+        // List the bookmarks
+        //
+        Some(Subcommand::Bookmark{cmd: BookmarkCmd::Ls{}}) => {
+            let bookmarks = Bookmarks::load_user()?;
+            for (name, bookmark) in bookmarks.iter() {
+                println!("@{:<20} {}", name, bookmark.to_terms().join(" "));
+            }
+            if let Some(shared) = load_shared_bookmarks() {
+                for (name, bookmark) in shared.iter().filter(|(name, _)| bookmarks.get(name).is_none()) {
+                    println!("@{:<20} {} (shared)", name, bookmark.to_terms().join(" "));
+                }
+            }
+        },
+        //
+        // List the navigation history
+        //
+        Some(Subcommand::History{count}) => {
+            let history = History::load()?;
+            let entries = history.entries();
+            let backs = history.back_counts(&std::env::current_dir()?);
+            let count = count.unwrap_or(entries.len()).min(entries.len());
+            for (entry, back) in entries.iter().zip(backs).skip(entries.len() - count) {
+                let time = chrono::Local.timestamp(entry.time, 0);
+                println!("{:>3}  {}  {:<24}  {}", 
+                    back.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string()), 
+                    time.format("%Y-%m-%d %H:%M"), 
+                    entry.levelspec.as_deref().unwrap_or("-"), 
+                    entry.path.display()
+                );
+            }
+        },
+        //
+        // Write the template to the cache
+        //
+        Some(Subcommand::Cache{path}) => {
+            let template = match graph {
+                Some(graph) => graph,
+                None => get_template_from_env()?,
+            };
+            let (graph,  _keymap,  _regexmap) = get_graph(Some(template.clone()))?;
+            let cache = graphcache::store(&template, &graph)?;
+            eprintln!("Cached {} as {}", template.display(), cache.display());
 
-                    };
-                    let search = vec![term];
-                    // todo handle abs path
-                    let mut validpath = cli::validpath_from_terms(search, &graph, false, false)?;
-                    let idx = keymap.get("show").unwrap();
-                    log::trace!("got index {:?}",idx );
-                    validpath.remove_past(idx)?;
-                    
-                    let mut pathbuf = validpath.pathbuf();
-                    pathbuf.push("etc");
-                    pathbuf.push("template.jspt");
-                    log::info!("Returning template {:?}", pathbuf);
-                    Ok( pathbuf)
-                })?
+            let path = match path {
+                Some(path) => diskutils::convert_relative_pathbuf_to_absolute(path)?,
+                None => std::env::current_dir()?,
             };
+            if let Some(template) = get_show_template_for_path(&path, &graph) {
+                let (graph,  _keymap,  _regexmap) = get_graph(Some(template.clone()))?;
+                let cache = graphcache::store(&template, &graph)?;
+                eprintln!("Cached {} as {}", template.display(), cache.display());
+            }
+        },
+        //
+        // Validate paths supplied on stdin, loading the graph only once
+        //
+        Some(Subcommand::Bulk{}) => {
+            let (graph,  _keymap,  _regexmap) = get_graph(graph)?;
+            let stdin = std::io::stdin();
+            let stdout = std::io::stdout();
+            let writer = std::io::BufWriter::new(stdout.lock());
+            bulk::validate_stream(stdin.lock(), writer, &graph)?;
+        },
+        //
+        // Complete the last word. Failures are silent, as there is nothing useful
+        // for the shell to do with them.
+        //
+        Some(Subcommand::Complete{line, mut words}) => {
+            let (graph,  _keymap,  _regexmap) = get_graph(graph)?;
+            let input = words.pop().unwrap_or_default();
+            let completions = match line {
+                Some(ref line) => complete::complete_line(line, &graph),
+                None => complete::complete(&input, &words, &graph),
+            };
+            match completions {
+                Ok(completions) => {
+                    for completion in completions.iter().filter(|c| c.is_insertable()) {
+                        println!("{}", completion.value);
+                    }
+                }
+                Err(e) => log::debug!("unable to complete {}: {}", input, e),
+            }
+        },
+        //
+        // Print the completion script for the requested shell
+        //
+        Some(Subcommand::Completion{myshell}) => {
+            let myshell = myshell.parse::<SupportedShell>()?.get();
+            print!("{}", myshell.completion_script(&["jspgo", "jspmk"]));
+        },
+        //
+        // Validate supplied argument to determine whether it is a valid path or not
+        //
+        None => {
 
-            if !terms.is_empty() && terms[0].contains('/')  {
-                let mut terms = PathBuf::from(&terms[0]);
-                terms = diskutils::convert_relative_pathbuf_to_absolute(terms)?;
-                match validate_path(&terms, &graph) {
-                    Ok(nodepath) => {
-                        report::validate_success(nodepath);
-                    },
-                    Err(JSPError::ValidationFailure{entry, node, depth}) => {
-                        report::failure(terms.as_os_str(), &entry, node, depth, &graph, true );
+            if dot.is_some() {
+                let (graph,  _keymap,  _regexmap) =  get_graph_main(
+                    terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(),
+                    graph
+                )?;
+            
+                if let Some(mut output) = dot {
+                    if !terms.is_empty(){
+                        log::warn!("INPUT not compatible with --dot argument. It will be ignored");
                     }
-                    Err(_) => panic!("JSPError type returned invalid")
+                    output = diskutils::convert_relative_pathbuf_to_absolute(output)?;
+                    // TODO: check to see that output doesnt exist and that its parent partory does exist
+                    diskutils::write_template_as_dotfile(&output, &graph);
+                } else {
+                    println!("{:#?}",  petgraph::dot::Dot::with_config(&graph, &[petgraph::dot::Config::EdgeNoLabel]));
                 }
-            } else {
+            } else if !terms.is_empty() {
 
-                let terms = gen_terms_from_strings(terms)?;
+                let (graph,  _keymap,  _regexmap) = get_graph_for_terms(graph, &terms, false, false)?;
 
-                match find::find_path_from_terms(terms, &graph) {
-                    Ok(( _path,  nodepath)) => { 
-                        report::validate_success(nodepath);
-                    },
-                    Err(e) => {
-                        return Err(e)?;
-                    },
-                };
+                if !terms.is_empty() && terms[0].contains('/')  {
+                    let mut terms = PathBuf::from(&terms[0]);
+                    terms = diskutils::convert_relative_pathbuf_to_absolute(terms)?;
+                    match validate_path(&terms, &graph) {
+                        Ok(nodepath) => {
+                            report::validate_success(nodepath);
+                        },
+                        Err(JSPError::ValidationFailure{entry, node, depth}) => {
+                            report::failure(terms.as_os_str(), &entry, node, depth, &graph, true );
+                        }
+                        Err(_) => panic!("JSPError type returned invalid")
+                    }
+                } else {
+
+                    let terms = gen_terms_from_strings(terms, &Levels::of(&graph))?;
+
+                    match find::find_path_from_terms(terms, &graph) {
+                        Ok(( _path,  nodepath)) => { 
+                            report::validate_success(nodepath);
+                        },
+                        Err(e) => {
+                            return Err(e)?;
+                        },
+                    };
+                }
+            //
+            // Don't know what you are thinking. I will print help and get out of your way
+            //
+            } else {
+                Opt::clap().print_help().unwrap();
             }
-        //
-        // Don't know what you are thinking. I will print help and get out of your way
-        //
-        } else {
-            Opt::clap().print_help().unwrap();
         }
     }
     Ok(())
 }
 
+// replace a reference to a bookmark, `@name`, with the bookmark's terms. The 
+// user's bookmarks take precedence over the current show's shared bookmarks
+fn resolve_bookmark(terms: Vec<String>) -> Result<Vec<String>, JSPError> {
+    if terms.len() != 1 {
+        return Ok(terms);
+    }
+    let name = match bookmark::parse_bookmark_reference(&terms[0]) {
+        Some(name) => name,
+        None => return Ok(terms),
+    };
+    let bookmarks = Bookmarks::load_user()?;
+    if let Some(bookmark) = bookmarks.get(name) {
+        return Ok(bookmark.to_terms());
+    }
+    load_shared_bookmarks()
+        .and_then(|shared| shared.get(name).map(Bookmark::to_terms))
+        .ok_or_else(|| JSPError::FindFailure(format!("No bookmark named '{}'", name)))
+}
+
+// load the shared bookmarks of the current show, if there is one. Failures are
+// logged, as the shared bookmarks are optional
+fn load_shared_bookmarks() -> Option<Bookmarks> {
+    let (graph, keymap, _regexmap) = match get_graph(None) {
+        Ok(graph) => graph,
+        Err(e) => {
+            log::warn!("unable to load shared bookmarks: {}", e);
+            return None;
+        }
+    };
+    // the current show is held by the variable named by the top level
+    let varname = Levels::of(&graph).iter().next().and_then(|level| level.varname.clone())?;
+    let show = std::env::var(varname).ok()?;
+    match get_show_etc_dir_from(&show, false, false, &graph, &keymap).and_then(|etc| Bookmarks::from_file(Bookmarks::shared_file(&etc))) {
+        Ok(bookmarks) => Some(bookmarks),
+        Err(e) => {
+            log::warn!("unable to load shared bookmarks for {}: {}", show, e);
+            None
+        }
+    }
+}
+
+// replace a reference to a previous location, `-` or `-N`, with the path recorded
+// in the navigation history
+fn resolve_back_reference(terms: Vec<String>, full_path: bool) -> Result<(Vec<String>, bool), JSPError> {
+    if terms.len() != 1 {
+        return Ok((terms, full_path));
+    }
+    match history::parse_back_reference(&terms[0]) {
+        Some(back) => {
+            let history = History::load()?;
+            let entry = history.previous(back, &std::env::current_dir()?).ok_or_else(|| 
+                JSPError::FindFailure(format!("No location {} back in the navigation history", back))
+            )?;
+            Ok((vec![entry.path.to_string_lossy().into_owned()], true))
+        }
+        None => Ok((terms, full_path)),
+    }
+}
+
+// retrieve the graph, keymap, and regexp for navigating to the location described
+// by terms. Unless a graph is supplied, the template is read from the show's etc 
+// directory
+fn get_graph_for_terms(graph: Option<PathBuf>, terms: &[String], full_path: bool, fuzzy: bool) 
+-> Result<(JGraph, JGraphKeyMap, RegexMap), JSPError> {
+    get_graph_from_fn(graph, &terms.iter().map(AsRef::as_ref).collect::<Vec<&str>>(), |_|{ 
+        let mut pathbuf = get_show_etc_dir(&terms[0], full_path, fuzzy)?;
+        pathbuf.push("template.jspt");
+        log::info!("Returning template {:?}", pathbuf);
+        Ok( pathbuf)
+    })
+}
+
+// retrieve the graph governing `path`, from the cache where possible. Unless a graph
+// is supplied, this is the template of the show containing the path, if it has one,
+// and otherwise the template named by JSP_PATH
+fn get_graph_for_path(graph: Option<PathBuf>, path: &Path) -> Result<JGraph, JSPError> {
+    if let Some(graph) = graph {
+        return graphcache::get_graph_cached(&graph);
+    }
+    let graph = graphcache::get_graph_cached(&get_template_from_env()?)?;
+    match get_show_template_for_path(path, &graph) {
+        Some(template) => graphcache::get_graph_cached(&template),
+        None => Ok(graph),
+    }
+}
+
 // retrieve the graph, keymap, and regexp
 fn get_graph_main(terms: Vec<&str>, graph: Option<PathBuf>) 
 -> Result<(JGraph, JGraphKeyMap, RegexMap), JSPError> {
@@ -278,7 +727,7 @@ fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
             ))
         })
         .level(level)
-        .chain(std::io::stdout())
+        .chain(std::io::stderr())
         .apply()?;
     Ok(())
 }
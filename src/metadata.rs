@@ -50,8 +50,16 @@ pub struct Metadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     varname: Option<String>,
     autocreate: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    navalias: Option<Navalias>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    navaliases: Vec<Navalias>,
+    #[serde(default)]
+    level: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    env: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    envfile: Option<String>,
 }
 
 impl std::default::Default for Metadata {
@@ -62,7 +70,11 @@ impl std::default::Default for Metadata {
             perms: None,
             varname: None,
             autocreate: false,
-            navalias: None,
+            navaliases: Vec::new(),
+            level: false,
+            entry: None,
+            env: Vec::new(),
+            envfile: None,
         }
     }
 }
@@ -89,7 +101,11 @@ impl Metadata {
             perms,
             varname,
             autocreate,
-            navalias
+            navaliases: navalias.into_iter().collect(),
+            level: false,
+            entry: None,
+            env: Vec::new(),
+            envfile: None,
         }
     }
 
@@ -247,41 +263,92 @@ impl Metadata {
         self
     }
 
+    /// Is the node a level of the job hierarchy
+    pub fn level(&self) -> bool {
+        self.level
+    }
+
+    /// set the level state
+    pub fn set_level(&mut self, level: bool) -> &mut Self {
+        self.level = level;
+        self
+    }
+
+    /// set the level state
+    pub fn set_owned_level(mut self, level: bool) ->  Self {
+        self.level = level;
+        self
+    }
+
+    /// Retrieve the entry name - a short name by which the node may be
+    /// addressed beneath a levelspec
+    pub fn entry(&self) -> Option<&str> {
+        self.entry.as_deref()
+    }
+
+    /// Set the entry name
+    pub fn set_entry(&mut self, entry: Option<String>) -> &mut Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Retrieve the environment variables to export, as pairs of name and 
+    /// value template
+    pub fn env(&self) -> &[(String, String)] {
+        &self.env
+    }
+
+    /// Add an environment variable to export, given its name and value template
+    pub fn add_env<I: Into<String>>(&mut self, name: I, value: I) -> &mut Self {
+        self.env.push((name.into(), value.into()));
+        self
+    }
+
+    /// Retrieve the env file, relative to the node's directory
+    pub fn envfile(&self) -> Option<&str> {
+        self.envfile.as_deref()
+    }
+
+    /// Set the env file
+    pub fn set_envfile(&mut self, envfile: Option<String>) -> &mut Self {
+        self.envfile = envfile;
+        self
+    }
+
     /// do we have a navalias
     pub fn has_navalias(&self) -> bool {
-        self.navalias().is_some()
+        !self.navaliases.is_empty()
     }
 
-    /// Set the navalias for Metadata
+    /// Set the navalias for Metadata, replacing any existing navaliases
     pub fn set_navalias(&mut self, navalias: Option<Navalias>) -> &mut Self {
         log::info!("Metadata.set_navalias({:?})", navalias);
-        self.navalias = navalias;
+        self.navaliases = navalias.into_iter().collect();
         self
     }
 
-    /// Set the navalias for Metadata
+    /// Set the navalias for Metadata, replacing any existing navaliases
     pub fn set_owned_navalias(mut self, navalias: Option<Navalias>) -> Self {
         log::info!("Metadata.set_navalias({:?})", navalias);
-        self.navalias = navalias;
+        self.navaliases = navalias.into_iter().collect();
         self
     }
 
-    /// Get the navalias
-    pub fn navalias(&self) -> &Option<Navalias> {
-       &self.navalias
+    /// Add a navalias to any existing navaliases
+    pub fn add_navalias(&mut self, navalias: Navalias) -> &mut Self {
+        log::info!("Metadata.add_navalias({:?})", navalias);
+        self.navaliases.push(navalias);
+        self
     }
 
-    /// Get the navalias
-    pub fn navalias_ref(&self) -> Option<&Navalias> {
-       match self.navalias {
-           Some(ref name) => Some(&name),
-           _ => None
-       }
+    /// Get the navaliases
+    pub fn navaliases(&self) -> &[Navalias] {
+       &self.navaliases
     }
 
-    /// Get a mutable navalias
-    pub fn navalias_mut(&mut self) -> &mut Option<Navalias> {
-        &mut self.navalias
+    /// Get a mutable reference to the navaliases
+    pub fn navaliases_mut(&mut self) -> &mut Vec<Navalias> {
+        &mut self.navaliases
     }
 
     /// given a mutable reference to self, create a 
@@ -308,4 +375,12 @@ mod metadata_term_tests {
         let md = Metadata::new().set_owned_navalias(na);
         assert_eq!(MetadataTerm::Navalias, md);
     }
+
+    #[test]
+    fn can_add_navaliases() {
+        let mut md = Metadata::new().set_owned_navalias(Some(Navalias::new_simple("cs")));
+        md.add_navalias(Navalias::new_complex("today", "logs/$DATE").with_priority(10));
+        assert_eq!(md.navaliases().len(), 2);
+        assert_eq!(md.navaliases()[1].priority, 10);
+    }
 }
\ No newline at end of file
@@ -1,25 +1,138 @@
+//! Navigation aliases, declared in the template via the `navalias` metadata key.
+//! A node may declare any number of them. Each alias has a name, an optional
+//! value, and a priority.
+//!
+//! An alias without a value changes directory to the node itself. An alias with
+//! a value changes directory to the value, relative to the node's parent. Values
+//! may reference the named captures, node names and varnames of the current
+//! location, environment variables, and `$DATE` (eg `navalias: today logs/$DATE`).
+//!
+//! When several nodes declare an alias of the same name, the alias with the
+//! highest priority wins (`navalias(10): cs work.$USER`). Aliases of equal
+//! priority resolve to the shortest path.
+use chrono::prelude::*;
 use serde::{ Deserialize, Serialize, self };
+use std::collections::BTreeMap;
+
+/// The name of the builtin `$DATE` variable, which expands to the current date
+pub const DATE_VAR: &str = "DATE";
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
-pub enum Navalias {
-    Simple(String),
-    Complex{name:String, value:String},
+pub struct Navalias {
+    /// The name of the alias
+    pub name: String,
+    /// The path of the alias, relative to the parent of the declaring node. If
+    /// absent, the alias changes directory to the declaring node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Aliases with a higher priority take precedence over those of the same name
+    #[serde(default)]
+    pub priority: i32,
 }
 
 impl Navalias {
-    /// Instantiate a new Navalias::Simple
-    pub fn new_simple<S>(name: S) -> Self 
+    /// Instantiate a new Navalias without a value
+    pub fn new_simple<S>(name: S) -> Self
     where
         S: Into<String>
     {
-        Navalias::Simple(name.into())
-    } 
+        Navalias{ name: name.into(), value: None, priority: 0 }
+    }
 
-    /// Instantiate a new Navalias::Complex 
-    pub fn new_complex<S>(name: S, value: S) -> Self 
+    /// Instantiate a new Navalias with a value
+    pub fn new_complex<S>(name: S, value: S) -> Self
     where
-        S: Into<String> 
+        S: Into<String>
     {
-        Navalias::Complex{name: name.into(), value: value.into()}
+        Navalias{ name: name.into(), value: Some(value.into()), priority: 0 }
+    }
+
+    /// Set the priority, consuming and returning self
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
     }
-}
\ No newline at end of file
+
+    /// Test whether this alias takes precedence over `other`, an alias of the
+    /// same name resolving to `other_path`, given that this alias resolves to
+    /// `path`.
+    pub fn supersedes(&self, path: &std::path::Path, other: &Navalias, other_path: &std::path::Path) -> bool {
+        self.priority > other.priority ||
+        (self.priority == other.priority && path.components().count() < other_path.components().count())
+    }
+}
+
+/// Expand the `$NAME` and `${NAME}` references within `value`. References are
+/// looked up in `values`, then `$DATE`, then the environment.
+///
+/// # Returns
+/// Ok wrapping the expanded value, or Err wrapping the name of the first
+/// reference which could not be resolved.
+pub fn expand_value(value: &str, values: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if braced && chars.next() != Some('}') {
+            return Err(format!("${{{}", name));
+        }
+        if name.is_empty() {
+            result.push('$');
+            continue;
+        }
+        if let Some(v) = values.get(&name) {
+            result.push_str(v);
+        } else if name == DATE_VAR {
+            let dt: DateTime<Local> = Local::now();
+            result.push_str(&dt.format("%Y%m%d").to_string());
+        } else if let Ok(v) = std::env::var(&name) {
+            result.push_str(&v);
+        } else {
+            return Err(name);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn can_expand_values() {
+        let mut values = BTreeMap::new();
+        values.insert("work".to_string(), "jgerber".to_string());
+        assert_eq!(expand_value("work.$work", &values), Ok("work.jgerber".to_string()));
+        assert_eq!(expand_value("${work}_dir", &values), Ok("jgerber_dir".to_string()));
+        assert_eq!(expand_value("logs/$DATE", &values).unwrap().len(), "logs/".len() + 8);
+        assert_eq!(expand_value("$JSP_NO_SUCH_VAR_SET", &values), Err("JSP_NO_SUCH_VAR_SET".to_string()));
+    }
+
+    #[test]
+    fn higher_priority_supersedes() {
+        let low = Navalias::new_simple("cs");
+        let high = Navalias::new_simple("cs").with_priority(10);
+        let short = Path::new("/dd/shows/DEV01");
+        let long = Path::new("/dd/shows/DEV01/RD/9999");
+        assert!(high.supersedes(long, &low, short));
+        assert!(!low.supersedes(short, &high, long));
+        assert!(low.supersedes(short, &low, long));
+    }
+}
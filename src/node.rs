@@ -1,4 +1,4 @@
-use crate::{ EntryType, Navalias, NodeType, User, Metadata};
+use crate::{ Dispatch, EntryType, Levels, Navalias, NodeType, User, Metadata};
 use serde::{ Deserialize, Serialize, self };
 
 #[allow(unused_imports)]
@@ -14,7 +14,11 @@ use std::{fmt::{ Display, Formatter, self} };
 pub struct Node {
     identity: NodeType,
     entry_type: EntryType,
-    metadata: Metadata
+    metadata: Metadata,
+    #[serde(skip)]
+    dispatch: Dispatch,
+    #[serde(skip)]
+    levels: Option<Levels>,
 }
 
 impl Display for Node {
@@ -56,7 +60,9 @@ impl Node {
         Self { 
             identity, 
             entry_type,
-            metadata:  Metadata::from_components(owner, group, perms, varname, autocreate, navalias)
+            metadata:  Metadata::from_components(owner, group, perms, varname, autocreate, navalias),
+            dispatch: Dispatch::default(),
+            levels: None,
         }
     }
 
@@ -65,6 +71,8 @@ impl Node {
             identity, 
             entry_type,
             metadata,
+            dispatch: Dispatch::default(),
+            levels: None,
         }
     }
 
@@ -74,6 +82,8 @@ impl Node {
             identity: NodeType::Root,
             entry_type: EntryType::Root,
             metadata: Metadata::new(),
+            dispatch: Dispatch::default(),
+            levels: None,
         }
     }
     /// Specialized constructor function which returns an Untracked node.
@@ -81,7 +91,9 @@ impl Node {
         Self {
             identity: NodeType::Untracked,
             entry_type: EntryType::Untracked,
-            metadata: Metadata::new()
+            metadata: Metadata::new(),
+            dispatch: Dispatch::default(),
+            levels: None,
         }
     }
 
@@ -103,6 +115,40 @@ impl Node {
         &self.identity
     }
 
+    /// Retrieve a reference to the dispatch index of the node's children
+    pub fn dispatch(&self) -> &Dispatch {
+        &self.dispatch
+    }
+
+    /// Set the dispatch index. This is done by `graph::build_dispatch_index`
+    /// once the graph has been loaded.
+    pub fn set_dispatch(&mut self, dispatch: Dispatch) {
+        self.dispatch = dispatch;
+    }
+
+    /// Retrieve the levels of the graph, if they have been recorded on the node.
+    /// They are recorded on the root by `graph::build_dispatch_index`.
+    pub fn levels(&self) -> Option<&Levels> {
+        self.levels.as_ref()
+    }
+
+    /// Record the levels of the graph on the node
+    pub fn set_levels(&mut self, levels: Levels) {
+        self.levels = Some(levels);
+    }
+
+    /// Return the name of the node. For Simple nodes, this is the name of the
+    /// directory. For RegEx nodes, it is the name assigned to the node in the
+    /// template.
+    pub fn name(&self) -> &str {
+        match &self.identity {
+            NodeType::Simple(n) => n.as_str(),
+            NodeType::RegEx{name, ..} => name.as_str(),
+            NodeType::Root => "root",
+            NodeType::Untracked => "untracked",
+        }
+    }
+
     /// Return a simplified name for the node.
     // TODO: add a simplename: Option<RefCell<String>> to Node to cache the simple name
     pub fn display_name(&self) -> String {
@@ -132,11 +178,28 @@ impl Node {
             //name.push_str(format!(" [{}]", n).as_str());
         }
 
+        if self.metadata().level() {
+            meta.push(String::from("level"));
+        }
+
+        if let Some(entry) = self.metadata().entry() {
+            meta.push(format!("entry:{}", entry));
+        }
+
 
-        if let Some(ref n) = self.metadata().navalias() {
-            match n {
-                Navalias::Simple(name) => meta.push(format!("navalias:{}", name)),
-                Navalias::Complex{name, value} => meta.push(format!("navalias:{} {}", name, value)),
+        for (name, value) in self.metadata().env() {
+            meta.push(format!("env:{}={}", name, value));
+        }
+
+        if let Some(envfile) = self.metadata().envfile() {
+            meta.push(format!("envfile:{}", envfile));
+        }
+
+        for navalias in self.metadata().navaliases() {
+            let tag = if navalias.priority != 0 { format!("navalias({})", navalias.priority) } else { String::from("navalias") };
+            match navalias.value {
+                None => meta.push(format!("{}:{}", tag, navalias.name)),
+                Some(ref value) => meta.push(format!("{}:{} {}", tag, navalias.name, value)),
             }
         }
 
@@ -238,7 +301,9 @@ mod node_tests {
         let expected = Node {
             identity: NodeType::Root,
             entry_type: EntryType::Root,
-            metadata: Metadata::new()
+            metadata: Metadata::new(),
+            dispatch: Dispatch::default(),
+            levels: None,
         };
         assert_eq!(root, expected);
     }
@@ -348,6 +348,28 @@ impl<'a> NodePath<'a> {
         })
     }
 
+    /// Generate a new NodePath by appending the nodes of `other` to
+    /// a copy of self. Both NodePaths are expected to refer to the same
+    /// JGraph; typically, `other` is the result of validating a relative path
+    /// from the leaf of self.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsp::{graph::testdata::build_graph, validate_path, validate_path_from};
+    ///
+    /// let graph = build_graph();
+    /// let shot = validate_path("/dd/shows/DEV01/RD/9999", &graph).unwrap();
+    /// let rel = validate_path_from("user", shot.nindex().unwrap(), &graph).unwrap();
+    /// let joined = shot.join(&rel);
+    /// assert_eq!(joined.len(), shot.len() + 1);
+    /// ```
+    pub fn join(&self, other: &NodePath) -> NodePath<'a> {
+        let mut nodepath = self.clone();
+        nodepath.nodes.extend_from_slice(&other.nodes);
+        nodepath
+    }
+
     /// Retrieve a reference to the JGraph
     /// 
     pub fn graph(&self) -> &'a JGraph {
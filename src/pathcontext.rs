@@ -0,0 +1,184 @@
+//! PathContext provides a structured view of the information encoded in
+//! a valid path - the values matched by each node, the values of the
+//! template variables, any named regex captures, and the resolved owner.
+use crate::{constants, get_default_user, JGraph, JSPError, Levels, NodePath, NodeType, User, ValidPath};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Component, Path, PathBuf},
+};
+
+/// The context extracted from a valid path.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct PathContext {
+    /// The path the context was extracted from
+    pub path: PathBuf,
+    /// Map of node names to the path component each node matched
+    pub nodes: BTreeMap<String, String>,
+    /// Map of template variable names (varnames) to values
+    pub vars: BTreeMap<String, String>,
+    /// Map of named regex captures to values
+    pub captures: BTreeMap<String, String>,
+    /// The owner of the leaf, as resolved from the nearest node defining an owner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// The levelspec of the path, made up of the values of the template's levels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levelspec: Option<String>,
+}
+
+impl PathContext {
+    /// New up a PathContext by validating the supplied path against the graph.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path to validate. Relative paths are converted to absolute paths.
+    /// * `graph` - Reference to the JGraph
+    ///
+    /// # Returns
+    /// Ok wrapping a PathContext if the path is valid. Otherwise, an Err wrapping a JSPError
+    pub fn new<I: Into<PathBuf>>(path: I, graph: &JGraph) -> Result<Self, JSPError> {
+        let validpath = ValidPath::new(path, graph, true)?;
+        Ok(Self::from_validpath(&validpath))
+    }
+
+    /// New up a PathContext from a ValidPath
+    pub fn from_validpath(validpath: &ValidPath) -> Self {
+        Self::from_nodepath(validpath.path(), validpath.nodepath())
+    }
+
+    /// New up a PathContext from a path and the NodePath it was validated against.
+    /// This does not validate the path; the caller is responsible for ensuring that
+    /// the two correspond.
+    pub fn from_nodepath(path: &Path, nodepath: &NodePath) -> Self {
+        let components = path_components(path);
+        let mut nodes = BTreeMap::new();
+        let mut vars = BTreeMap::new();
+        let mut captures = BTreeMap::new();
+        let mut owner = None;
+
+        for (node, value) in nodepath.iter().zip(components.iter()) {
+            match node.identity() {
+                NodeType::Simple(_) | NodeType::RegEx{..} => {
+                    nodes.insert(node.name().to_string(), value.clone());
+                }
+                _ => (),
+            }
+            if let NodeType::RegEx{pattern, ..} = node.identity() {
+                if let Some(caps) = pattern.captures(value) {
+                    for name in pattern.capture_names().flatten() {
+                        if let Some(m) = caps.name(name) {
+                            captures.insert(name.to_string(), m.as_str().to_string());
+                        }
+                    }
+                }
+            }
+            if let Some(varname) = node.metadata().varname_ref() {
+                vars.insert(varname.to_string(), value.clone());
+            }
+            // nodes deeper in the path take precedence over their ancestors
+            if let Some(user) = node.metadata().owner_ref() {
+                owner = resolve_owner(user, &captures);
+            }
+        }
+
+        let levelspec = Levels::of(nodepath.graph()).levelspec(|level| nodes.get(level).map(|v| v.as_str()));
+
+        Self {
+            path: path.to_path_buf(),
+            nodes,
+            vars,
+            captures,
+            owner,
+            levelspec,
+        }
+    }
+
+    /// Retrieve the value matched by the node named `name`
+    pub fn node(&self, name: &str) -> Option<&str> {
+        self.nodes.get(name).map(|x| x.as_str())
+    }
+
+    /// Retrieve the value of the template variable `varname`
+    pub fn var(&self, varname: &str) -> Option<&str> {
+        self.vars.get(varname).map(|x| x.as_str())
+    }
+
+    /// Retrieve the value of the named regex capture `name`
+    pub fn capture(&self, name: &str) -> Option<&str> {
+        self.captures.get(name).map(|x| x.as_str())
+    }
+
+    /// Retrieve the resolved owner
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// Retrieve the levelspec (eg `DEV01.RD.9999`), made up of the values of the 
+    /// template's levels, in order. None if the path does not reach the top level.
+    pub fn levelspec(&self) -> Option<String> {
+        self.levelspec.clone()
+    }
+}
+
+/// Split a path into a vector of Strings, one per component. The root
+/// component is represented by "/".
+pub fn path_components(path: &Path) -> Vec<String> {
+    path.components().map(|x| {
+        match x {
+            Component::RootDir => String::from("/"),
+            Component::Normal(level) => level.to_string_lossy().to_string(),
+            Component::CurDir => String::from("."),
+            Component::ParentDir => String::from(".."),
+            Component::Prefix(_) => String::new(),
+        }
+    }).collect()
+}
+
+// resolve a User to a user name, using the captures for User::Captured
+fn resolve_owner(user: &User, captures: &BTreeMap<String, String>) -> Option<String> {
+    match user {
+        User::Named(name) => Some(name.clone()),
+        User::Me => Some(env::var(constants::USER_ENV_VAR).unwrap_or_else(|_| get_default_user())),
+        User::Captured(key) => captures.get(key).cloned(),
+        User::Uid(uid) => Some(uid.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph;
+
+    #[test]
+    fn can_extract_context() {
+        let graph = build_graph();
+        let ctx = PathContext::new("/dd/shows/DEV01/RD/9999/user/work.jgerber", &graph).unwrap();
+        assert_eq!(ctx.node("show"), Some("DEV01"));
+        assert_eq!(ctx.node("sequence"), Some("RD"));
+        assert_eq!(ctx.node("shot"), Some("9999"));
+        assert_eq!(ctx.node("work"), Some("work.jgerber"));
+        assert_eq!(ctx.var("DD_SHOW"), Some("DEV01"));
+        assert_eq!(ctx.var("DD_WORK"), Some("work.jgerber"));
+        assert_eq!(ctx.capture("work"), Some("jgerber"));
+        assert_eq!(ctx.owner(), Some("jgerber"));
+        assert_eq!(ctx.levelspec(), Some("DEV01.RD.9999".to_string()));
+    }
+
+    #[test]
+    fn invalid_path_is_err() {
+        let graph = build_graph();
+        let ctx = PathContext::new("/dd/shows/DEV01/RD/9999/FOO", &graph);
+        assert!(ctx.is_err());
+    }
+
+    #[test]
+    fn can_serialize_context() {
+        let graph = build_graph();
+        let ctx = PathContext::new("/dd/shows/DEV01/RD", &graph).unwrap();
+        let json = serde_json::to_string(&ctx).unwrap();
+        let ctx2: PathContext = serde_json::from_str(&json).unwrap();
+        assert_eq!(ctx, ctx2);
+    }
+}
@@ -0,0 +1,353 @@
+//! Build paths from a map of named values. This is the inverse of validation;
+//! rather than extracting values from a path, we supply values for the nodes
+//! (or the named captures within their regular expressions) and render
+//! every path through the template which leads to the target node.
+//!
+//! Values are looked up, for each RegEx node, first by the node's name, then
+//! by its varname. Failing that, each named capture within the node's regular
+//! expression is looked up by name and substituted into the expression.
+use crate::{JGraph, JSPError, NIndex, NodePath, NodeType, ValidPath};
+use petgraph::{visit::IntoNodeReferences, Direction::Outgoing};
+use std::{collections::HashMap, path::PathBuf};
+
+// The reason a candidate path could not be rendered
+enum CandidateError {
+    Missing(Vec<String>),
+    Invalid(String),
+}
+
+/// Generate every valid path which terminates in the node named `target`, given
+/// a map of values.
+///
+/// # Parameters
+///
+/// * `target` - The name of the node which the path should terminate in
+/// * `values` - Map of node names, varnames, or named captures, to values
+/// * `graph`  - Reference to the JGraph
+///
+/// # Returns
+/// Ok wrapping a vector of every ValidPath which may be rendered from the values,
+/// in graph order. Otherwise, a JSPError. If no path may be rendered, the error
+/// names the values missing from the closest candidate.
+///
+/// # Example
+///
+/// ```
+/// use jsp::{graph::testdata::build_graph, pathformat::format_paths};
+/// use std::collections::HashMap;
+///
+/// let graph = build_graph();
+/// let mut values = HashMap::new();
+/// values.insert("show".to_string(), "DEV01".to_string());
+/// values.insert("sequence".to_string(), "RD".to_string());
+/// values.insert("shot".to_string(), "9999".to_string());
+/// let paths = format_paths("shot", &values, &graph).unwrap();
+/// assert_eq!(paths[0].path().to_str(), Some("/dd/shows/DEV01/RD/9999"));
+/// ```
+pub fn format_paths<'a>(target: &str, values: &HashMap<String, String>, graph: &'a JGraph)
+    -> Result<Vec<ValidPath<'a>>, JSPError>
+{
+    log::info!("format_paths(target: {}, values: {:?}, graph)", target, values);
+    let root = match graph.node_references().next() {
+        Some((idx, _)) => idx,
+        None => return Err(JSPError::FormatFailure("graph is empty".to_string())),
+    };
+
+    let mut candidates = Vec::new();
+    let mut current = vec![root];
+    collect_candidates(root, target, graph, &mut current, &mut candidates);
+    if candidates.is_empty() {
+        return Err(JSPError::FormatFailure(format!("no node named '{}' in template", target)));
+    }
+
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    for candidate in candidates {
+        match render_nodes(&candidate, values, graph) {
+            Ok(pathbuf) => {
+                let nodepath = NodePath::new(graph).replace_nodes_unchecked(candidate);
+                results.push(ValidPath::new_unchecked(pathbuf, nodepath, false)?);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !results.is_empty() {
+        return Ok(results);
+    }
+
+    // report the candidate which is missing the fewest values. If none of the
+    // candidates are missing values, report the first invalid value instead.
+    let closest = errors.iter().filter_map(|e| match e {
+        CandidateError::Missing(m) => Some(m),
+        _ => None,
+    }).min_by_key(|m| m.len());
+
+    if let Some(missing) = closest {
+        return Err(JSPError::MissingFormatValues{ target: target.to_string(), missing: missing.clone() });
+    }
+    for e in errors {
+        if let CandidateError::Invalid(msg) = e {
+            return Err(JSPError::FormatFailure(msg));
+        }
+    }
+    Err(JSPError::FormatFailure(format!("unable to render path for '{}'", target)))
+}
+
+/// Generate the unique valid path which terminates in the node named `target`, given
+/// a map of values.
+///
+/// # Parameters
+///
+/// * `target` - The name of the node which the path should terminate in
+/// * `values` - Map of node names, varnames, or named captures, to values
+/// * `graph`  - Reference to the JGraph
+///
+/// # Returns
+/// Ok wrapping the ValidPath if exactly one path may be rendered. If more than one path
+/// may be rendered, a JSPError::AmbiguousFormat listing the candidates. Otherwise, the
+/// error returned by `format_paths`.
+pub fn format_path<'a>(target: &str, values: &HashMap<String, String>, graph: &'a JGraph)
+    -> Result<ValidPath<'a>, JSPError>
+{
+    let mut paths = format_paths(target, values, graph)?;
+    if paths.len() > 1 {
+        return Err(JSPError::AmbiguousFormat{
+            target: target.to_string(),
+            candidates: paths.iter().map(|p| p.pathbuf()).collect(),
+        });
+    }
+    Ok(paths.remove(0))
+}
+
+// Depth first traversal of the graph, collecting every path from the root to
+// a node named target.
+fn collect_candidates(
+    idx: NIndex,
+    target: &str,
+    graph: &JGraph,
+    current: &mut Vec<NIndex>,
+    candidates: &mut Vec<Vec<NIndex>>
+) {
+    if graph[idx].name() == target && idx != current[0] {
+        candidates.push(current.clone());
+        return;
+    }
+    // neighbors are returned in reverse order of insertion
+    let mut neighbors = graph.neighbors_directed(idx, Outgoing).collect::<Vec<NIndex>>();
+    neighbors.reverse();
+    for neighbor in neighbors {
+        if current.contains(&neighbor) {
+            continue;
+        }
+        current.push(neighbor);
+        collect_candidates(neighbor, target, graph, current, candidates);
+        current.pop();
+    }
+}
+
+// Render the path for a list of node indices from the values
+fn render_nodes(nodes: &[NIndex], values: &HashMap<String, String>, graph: &JGraph)
+    -> Result<PathBuf, CandidateError>
+{
+    let mut path = PathBuf::new();
+    let mut missing = Vec::new();
+
+    for idx in nodes {
+        let node = &graph[*idx];
+        match node.identity() {
+            NodeType::Root => path.push("/"),
+            NodeType::Simple(name) => path.push(name),
+            NodeType::RegEx{name, pattern, exclude} => {
+                let direct = values.get(name.as_str())
+                    .or_else(|| node.metadata().varname_ref().and_then(|v| values.get(v)));
+                let value = match direct {
+                    Some(value) if pattern.is_match(value) => value.clone(),
+                    _ => match render_pattern(pattern.as_str(), values) {
+                        Ok(value) => value,
+                        // a value which does not match is reported below
+                        Err(_) if direct.is_some() => direct.unwrap().clone(),
+                        Err(mut m) => {
+                            if m.is_empty() { m.push(name.clone()); }
+                            for key in m {
+                                if !missing.contains(&key) { missing.push(key); }
+                            }
+                            continue;
+                        }
+                    }
+                };
+                if !pattern.is_match(&value) {
+                    return Err(CandidateError::Invalid(
+                        format!("value '{}' for '{}' does not match '{}'", value, name, pattern.as_str())
+                    ));
+                }
+                if let Some(exclude) = exclude {
+                    if exclude.is_match(&value) {
+                        return Err(CandidateError::Invalid(
+                            format!("value '{}' for '{}' is excluded by '{}'", value, name, exclude.as_str())
+                        ));
+                    }
+                }
+                path.push(value);
+            }
+            NodeType::Untracked => {
+                return Err(CandidateError::Invalid("Untracked node in template".to_string()));
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(path)
+    } else {
+        Err(CandidateError::Missing(missing))
+    }
+}
+
+/// Render a regular expression by replacing each named capture group with the
+/// corresponding value, removing anchors, and unescaping literals. The result
+/// should be checked against the regular expression by the caller, as any
+/// regex syntax outside of the capture groups is not rendered.
+///
+/// # Returns
+/// Ok wrapping the rendered String. Otherwise, an Err wrapping the names of
+/// the captures which are missing from values. If the expression has no named
+/// captures, the vector is empty.
+pub fn render_pattern(pattern: &str, values: &HashMap<String, String>) -> Result<String, Vec<String>> {
+    let chars = pattern.chars().collect::<Vec<char>>();
+    let mut out = String::new();
+    let mut missing = Vec::new();
+    let mut captures = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            out.push(chars[i + 1]);
+            i += 2;
+        } else if chars[i..].starts_with(&['(', '?', 'P', '<']) {
+            let name_start = i + 4;
+            let name_end = match chars[name_start..].iter().position(|c| *c == '>') {
+                Some(pos) => name_start + pos,
+                None => break,
+            };
+            let name = chars[name_start..name_end].iter().collect::<String>();
+            captures += 1;
+            match values.get(&name) {
+                Some(value) => out.push_str(value),
+                None => missing.push(name),
+            }
+            i = match group_end(&chars, i) {
+                Some(end) => end + 1,
+                None => chars.len(),
+            };
+        } else if c == '^' || c == '$' {
+            i += 1;
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+
+    if captures == 0 {
+        return Err(Vec::new());
+    }
+    if missing.is_empty() { Ok(out) } else { Err(missing) }
+}
+
+// Given the index of an opening paren, find the index of the matching closing paren,
+// skipping escaped characters and character classes.
+fn group_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_class = false;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => { i += 1; }
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn can_render_pattern_with_multiple_captures() {
+        let vals = values(&[("first", "foo"), ("second", "bar")]);
+        let result = render_pattern(r"^(?P<first>[a-z]+)_v(?P<second>[a-z]+(_[0-9]+)?)\.ext$", &vals);
+        assert_eq!(result, Ok("foo_vbar.ext".to_string()));
+    }
+
+    #[test]
+    fn render_pattern_reports_missing_captures() {
+        let vals = values(&[("first", "foo")]);
+        let result = render_pattern(r"^(?P<first>[a-z]+)_(?P<second>[a-z]+)$", &vals);
+        assert_eq!(result, Err(vec!["second".to_string()]));
+    }
+
+    #[test]
+    fn can_format_path_with_capture() {
+        let graph = build_graph();
+        let vals = values(&[("show", "DEV01"), ("sequence", "RD"), ("shot", "9999"), ("work", "jgerber")]);
+        let path = format_path("work", &vals, &graph).unwrap();
+        assert_eq!(path.pathbuf(), PathBuf::from("/dd/shows/DEV01/RD/9999/user/work.jgerber"));
+    }
+
+    #[test]
+    fn can_format_all_paths() {
+        let graph = build_graph();
+        let vals = values(&[
+            ("show", "DEV01"), ("dept", "MODEL"), ("category", "char"),
+            ("department", "model"), ("subcontext", "bob")
+        ]);
+        let paths = format_paths("subcontext", &vals, &graph).unwrap()
+                    .iter().map(|p| p.pathbuf()).collect::<Vec<PathBuf>>();
+        assert!(paths.contains(&PathBuf::from("/dd/shows/DEV01/SHARED/MODEL/char/model/bob")));
+        assert!(paths.contains(&PathBuf::from("/dd/shows/DEV01/CONFORM/SHARED/MODEL/char/model/bob")));
+        assert_eq!(paths.len(), 2);
+        let result = format_path("subcontext", &vals, &graph);
+        match result {
+            Err(JSPError::AmbiguousFormat{ candidates, .. }) => assert_eq!(candidates.len(), 2),
+            _ => panic!("expected AmbiguousFormat"),
+        }
+    }
+
+    #[test]
+    fn missing_values_are_named() {
+        let graph = build_graph();
+        let vals = values(&[("show", "DEV01")]);
+        let result = format_path("shot", &vals, &graph);
+        assert_eq!(
+            result.unwrap_err(),
+            JSPError::MissingFormatValues{
+                target: "shot".to_string(),
+                missing: vec!["sequence".to_string(), "shot".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_value_is_err() {
+        let graph = build_graph();
+        let vals = values(&[("show", "dev01"), ("sequence", "RD"), ("shot", "9999")]);
+        let result = format_path("shot", &vals, &graph);
+        assert!(result.is_err());
+    }
+}
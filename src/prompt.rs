@@ -0,0 +1,133 @@
+//! Render a compact description of the current location for use in a shell
+//! prompt, eg `DEV01:RD:9999 [work.jgerber]`. The location is worked out from
+//! the path, via `Location`, rather than the environment.
+//!
+//! The format is a string containing `{token}`s. The supported tokens are:
+//!
+//! * `{levels}` - the levelspec, separated by `:` (eg `DEV01:RD:9999`)
+//! * `{levelspec}` - the levelspec (eg `DEV01.RD.9999`)
+//! * `{leaf}` - the last directory of the location, if it is below the deepest level
+//! * `{owner}` - the owner of the location
+//! * `{untracked}` - the number of directories beyond the location, if any
+//! * `{location}` - the location
+//! * `{<name>}` - the value matched by the node named `name` (eg `{show}`), or 
+//!   failing that, the named capture or template variable `name`
+//!
+//! Text between `[` and `]` is only rendered if at least one of the tokens 
+//! within it has a value.
+use crate::{JGraph, Location};
+use std::path::Path;
+
+/// The format used when none is supplied
+pub const DEFAULT_PROMPT_FORMAT: &str = "{levels} [{leaf}]";
+
+/// Render the prompt for `path`. Prompts are drawn constantly, so failure is
+/// not an option; None is returned if the path has no valid location.
+///
+/// # Parameters
+///
+/// * `format` - The format string
+/// * `path` - The path to describe, typically the current working directory
+/// * `graph` - Reference to the JGraph
+pub fn prompt(format: &str, path: &Path, graph: &JGraph) -> Option<String> {
+    match Location::new(path, graph) {
+        Ok(location) => Some(render(format, &location)),
+        Err(e) => {
+            log::debug!("prompt(...) no location for {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Render the format for the supplied location. Unknown tokens render as nothing.
+pub fn render(format: &str, location: &Location) -> String {
+    let mut out = String::new();
+    // the text of the current [group], and whether any of its tokens has a value
+    let mut group: Option<(String, bool)> = None;
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let name = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                let value = token(&name, location).unwrap_or_default();
+                match group {
+                    Some((ref mut text, ref mut has_value)) => {
+                        *has_value |= !value.is_empty();
+                        text.push_str(&value);
+                    }
+                    None => out.push_str(&value),
+                }
+            }
+            '[' if group.is_none() => group = Some((String::from("["), false)),
+            ']' if group.is_some() => {
+                if let Some((mut text, true)) = group.take() {
+                    text.push(']');
+                    out.push_str(&text);
+                }
+            }
+            _ => match group {
+                Some((ref mut text, _)) => text.push(c),
+                None => out.push(c),
+            },
+        }
+    }
+    // an unterminated group is rendered as is
+    if let Some((text, _)) = group {
+        out.push_str(&text);
+    }
+    out.trim().to_string()
+}
+
+// retrieve the value of a token
+fn token(name: &str, location: &Location) -> Option<String> {
+    let context = &location.context;
+    match name {
+        "levels" => location.levelspec.as_ref().map(|ls| ls.replace('.', ":")),
+        "levelspec" => location.levelspec.clone(),
+        "leaf" => leaf(location),
+        "owner" => context.owner().map(|o| o.to_string()),
+        "untracked" => if location.untracked > 0 { Some(location.untracked.to_string()) } else { None },
+        "location" => Some(location.location().display().to_string()),
+        _ => context.node(name)
+                    .or_else(|| context.capture(name))
+                    .or_else(|| context.var(name))
+                    .map(|v| v.to_string()),
+    }
+}
+
+// the last directory of the location, unless the location is a level
+fn leaf(location: &Location) -> Option<String> {
+    let last = location.location().file_name()?.to_string_lossy().to_string();
+    let is_level = location.levelspec.as_ref().and_then(|ls| ls.rsplit('.').next()) == Some(last.as_str());
+    if is_level { None } else { Some(last) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::testdata::build_graph;
+
+    #[test]
+    fn can_render_default_prompt() {
+        let graph = build_graph();
+        let path = Path::new("/dd/shows/DEV01/RD/9999/user/work.jgerber");
+        assert_eq!(prompt(DEFAULT_PROMPT_FORMAT, path, &graph), Some("DEV01:RD:9999 [work.jgerber]".to_string()));
+        let path = Path::new("/dd/shows/DEV01/RD/9999");
+        assert_eq!(prompt(DEFAULT_PROMPT_FORMAT, path, &graph), Some("DEV01:RD:9999".to_string()));
+    }
+
+    #[test]
+    fn can_render_tokens() {
+        let graph = build_graph();
+        let path = Path::new("/dd/shows/DEV01/RD/9999/user/work.jgerber/scenes");
+        let prompt = prompt("{show}/{shot} {work}({owner}) [+{untracked}] [{nope}]", path, &graph);
+        assert_eq!(prompt, Some("DEV01/9999 work.jgerber(jgerber) [+1]".to_string()));
+    }
+
+    #[test]
+    fn invalid_path_is_none() {
+        let graph = build_graph();
+        assert_eq!(prompt(DEFAULT_PROMPT_FORMAT, Path::new("/tmp"), &graph), None);
+    }
+}
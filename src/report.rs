@@ -166,6 +166,26 @@ pub fn shellinfo<T>(info: T,verbose: bool) where T: AsRef<str> + std::fmt::Displ
     }
 }
 
+/// Use to print out a warning that works with our bash script wrapper
+/// 
+/// # Parameters
+/// 
+/// * `info` - The warning, provided by any type which may be converted to a &str
+///            via as_ref(), and which implements the Display trait. 
+/// * `verbose` - Whether to print out verbose info or not 
+/// 
+/// # Returns
+/// None
+pub fn shellwarning<T>(info: T,verbose: bool) where T: AsRef<str> + std::fmt::Display {
+    if verbose { 
+        eprintln!("\n{}\n", "Warning".bright_yellow()); 
+        eprintln!("\t'{}'", info.as_ref());
+        eprintln!();
+    } else {
+        eprintln!("{} '{}'", "Warning".bright_yellow(), info.as_ref());
+    }
+}
+
 /*
 pub(crate) fn go_failure(path_str: &str, myshell: bool, verbose: bool) {
     let cr = if verbose { "\n" } else {""};
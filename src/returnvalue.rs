@@ -1,8 +1,6 @@
 use petgraph::graph::{ DefaultIx, NodeIndex };
 use std::ffi::OsString;
 use std::cmp::Ordering;
-use std::cell::RefCell;
-use std::rc::Rc;
 pub type NIndex = NodeIndex<DefaultIx>;
 
 /// Used to capture the success or failure of a comparison
@@ -23,13 +21,13 @@ pub type NIndex = NodeIndex<DefaultIx>;
 ///             requested path.
 #[derive(Debug, PartialEq, Eq)]
 pub enum ReturnValue {
-    Success(Rc<RefCell<Vec<NIndex>>>),
+    Success(Vec<NIndex>),
     Failure{ entry: OsString, node: NIndex, depth: u8 }
 }
 
 impl ReturnValue {
     pub fn new_success(val: Vec<NIndex>) -> ReturnValue {
-        ReturnValue::Success(Rc::new(RefCell::new(val)))
+        ReturnValue::Success(val)
     }
 
     /// Is the current ReturnValue instance a Success?
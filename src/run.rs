@@ -0,0 +1,130 @@
+//! Build the process which `jsp run` execs - the supplied command, run from
+//! the target directory of a GoPlan, with the template's environment variables 
+//! set, stale ones removed, and each navalias exported as a `JSP_NAV_<NAME>` 
+//! environment variable, as a child process cannot inherit aliases.
+use crate::{GoPlan, JSPError};
+use nix::unistd::{getegid, geteuid, getgid, getuid, setgid, setuid};
+use std::process::Command;
+
+/// The prefix of the environment variables which hold the navalias paths
+pub const NAV_ENV_PREFIX: &str = "JSP_NAV_";
+
+/// Retrieve the name of the environment variable which holds the path of the 
+/// navalias `name`. The name is upper cased, and anything other than ascii 
+/// letters and digits is replaced by an underscore. 
+pub fn nav_env_var(name: &str) -> String {
+    let name = name.chars()
+                   .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+                   .collect::<String>();
+    format!("{}{}", NAV_ENV_PREFIX, name)
+}
+
+/// Build a Command which runs `command` in the plan's target directory, with the
+/// plan's environment.
+/// 
+/// # Parameters
+/// 
+/// * `plan`    - The GoPlan describing the location
+/// * `command` - The command to run, followed by its arguments
+/// 
+/// # Returns
+/// Ok wrapping the Command if successful. Otherwise, a JSPError if the command 
+/// is empty, or the target directory does not exist.
+pub fn command(plan: &GoPlan, command: &[String]) -> Result<Command, JSPError> {
+    let (program, args) = command.split_first().ok_or(JSPError::EmptyArgumentListError)?;
+    if !plan.target.is_dir() {
+        return Err(JSPError::NonExtantPathError(plan.target.clone()));
+    }
+    let mut cmd = Command::new(program);
+    cmd.args(args).current_dir(&plan.target);
+
+    for varname in &plan.unset_vars {
+        cmd.env_remove(varname);
+    }
+    // remove navaliases exported for another location
+    for (varname, _) in std::env::vars() {
+        if varname.starts_with(NAV_ENV_PREFIX) {
+            cmd.env_remove(varname);
+        }
+    }
+    cmd.envs(&plan.set_vars);
+    for (name, path) in &plan.set_aliases {
+        cmd.env(nav_env_var(name), path);
+    }
+    cmd.env("PWD", &plan.target);
+    Ok(cmd)
+}
+
+/// Drop the privileges granted to the process by the setuid and setgid bits, 
+/// by setting the effective user and group ids to the real ones. `jsp run` does
+/// so before exec'ing the command, which would otherwise run as the owner of
+/// the executable. Directories are created beforehand, by `jspmk`.
+/// 
+/// # Returns
+/// Ok if the effective ids match the real ids. Otherwise, a JSPError.
+pub fn drop_privileges() -> Result<(), JSPError> {
+    let (uid, gid) = (getuid(), getgid());
+    // the group first, as setting it may require the privileges being dropped
+    if getegid() != gid {
+        setgid(gid)?;
+    }
+    if geteuid() != uid {
+        setuid(uid)?;
+    }
+    if geteuid() != uid || getegid() != gid {
+        return Err(JSPError::GeneralError(
+            format!("unable to drop privileges to uid {} and gid {}", uid, gid)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, env, ffi::OsStr, path::PathBuf};
+
+    #[test]
+    fn can_name_nav_env_var() {
+        assert_eq!(nav_env_var("cs"), "JSP_NAV_CS");
+        assert_eq!(nav_env_var("cs.work-1"), "JSP_NAV_CS_WORK_1");
+    }
+
+    #[test]
+    fn command_has_plan_environment() {
+        let target = env::temp_dir();
+        let mut plan = GoPlan::new(&target);
+        plan.set_vars.insert("DD_SHOW".to_string(), "DEV01".to_string());
+        plan.unset_vars.push("DD_SHOT".to_string());
+        plan.set_aliases.insert("cs".to_string(), PathBuf::from("/dd/shows/DEV01/SHARED"));
+
+        let cmd = command(&plan, &["maya".to_string(), "-batch".to_string()]).unwrap();
+        assert_eq!(cmd.get_program(), "maya");
+        assert_eq!(cmd.get_args().collect::<Vec<&OsStr>>(), vec!["-batch"]);
+        assert_eq!(cmd.get_current_dir(), Some(target.as_path()));
+        let envs = cmd.get_envs().collect::<HashMap<&OsStr, Option<&OsStr>>>();
+        assert_eq!(envs.get(OsStr::new("DD_SHOW")), Some(&Some(OsStr::new("DEV01"))));
+        assert_eq!(envs.get(OsStr::new("DD_SHOT")), Some(&None));
+        assert_eq!(envs.get(OsStr::new("JSP_NAV_CS")), Some(&Some(OsStr::new("/dd/shows/DEV01/SHARED"))));
+    }
+
+    #[test]
+    fn empty_command_is_err() {
+        let plan = GoPlan::new(env::temp_dir());
+        assert_eq!(command(&plan, &[]).err(), Some(JSPError::EmptyArgumentListError));
+    }
+
+    #[test]
+    fn can_drop_privileges() {
+        // a no-op unless the test runs setuid or setgid
+        drop_privileges().unwrap();
+        assert_eq!(geteuid(), getuid());
+        assert_eq!(getegid(), getgid());
+    }
+
+    #[test]
+    fn missing_target_is_err() {
+        let plan = GoPlan::new("/this/does/not/exist");
+        assert!(command(&plan, &["ls".to_string()]).is_err());
+    }
+}
@@ -3,12 +3,16 @@ use std::path::Path;
 use crate::JSPError;
 
 pub mod bash;
+pub mod fish;
 pub mod tcsh;
+pub mod zsh;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum SupportedShell {
     Bash,
-    Tcsh
+    Fish,
+    Tcsh,
+    Zsh,
 }
 
 impl SupportedShell {
@@ -16,7 +20,9 @@ impl SupportedShell {
     pub fn get(&self) -> Box<dyn ShellEnvManager> {
         match self {
             SupportedShell::Bash => Box::new(bash::Shell::new()),
+            SupportedShell::Fish => Box::new(fish::Shell::new()),
             SupportedShell::Tcsh => Box::new(tcsh::Shell::new()),
+            SupportedShell::Zsh => Box::new(zsh::Shell::new()),
         }
     }
 }
@@ -27,7 +33,9 @@ impl FromStr for SupportedShell {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "bash" => Ok(SupportedShell::Bash),
+            "fish" => Ok(SupportedShell::Fish),
             "tcsh" => Ok(SupportedShell::Tcsh),
+            "zsh" => Ok(SupportedShell::Zsh),
             _ => Err(JSPError::UnknownShell(s.to_string())),
         }
     }
@@ -36,7 +44,14 @@ impl FromStr for SupportedShell {
 /// Basic methods for setting environment variables for a target Shell. These
 /// are used by the `jsp go` to print commands to stdout, which will later be 
 /// `eval`ed in the shell in order to mutate the existing environment. 
+///
+/// Every value interpolated into the generated code must go through `quote`, 
+/// and every name must pass `is_var_name` or `is_alias_name`, as the code is 
+/// `eval`ed and paths may contain anything a directory name may.
 pub trait ShellEnvManager {
+    /// Quote `value` such that the Shell treats it as a single literal word.
+    fn quote(&self, value: &str) -> String;
+
     /// Generate a string that sets an environment variable named `varname` to  `value`
     /// in a specific Shell, as dictated by the concrete implementation of the trait.
     fn set_env_var(&self, varname: &str, value: &str) -> String;
@@ -55,4 +70,96 @@ pub trait ShellEnvManager {
     /// Generate a string which unsets an alias
     fn unset_alias(&self, name: &str) -> String;
 
-}
\ No newline at end of file
+    /// Generate a script which registers completion for each of the supplied
+    /// commands. The script calls `jsp complete --line` with the command line
+    /// being completed.
+    fn completion_script(&self, commands: &[&str]) -> String;
+
+}
+
+/// Is `name` usable as an environment variable name? Names which are not are
+/// never emitted.
+pub fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// Is `name` usable as an alias name? Names which are not are never emitted.
+pub fn is_alias_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('-') 
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Can `value` be emitted without quoting in any of the supported shells?
+pub fn is_bare_word(value: &str) -> bool {
+    !value.is_empty() 
+    && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:+,".contains(c))
+}
+
+/// Quote `value` for a POSIX shell (bash or zsh) by wrapping it in single quotes.
+/// Single quotes within the value are closed, escaped, and reopened.
+pub fn posix_quote(value: &str) -> String {
+    if is_bare_word(value) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// Log and skip a name which cannot be used safely
+pub(crate) fn reject_name(kind: &str, name: &str) -> String {
+    log::warn!("Skipping {} with unsafe name: {:?}", kind, name);
+    String::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CachedAliases, CachedEnvVars};
+
+    #[test]
+    fn can_parse_supported_shells() {
+        assert_eq!(SupportedShell::from_str("bash").unwrap(), SupportedShell::Bash);
+        assert_eq!(SupportedShell::from_str("Fish").unwrap(), SupportedShell::Fish);
+        assert_eq!(SupportedShell::from_str("tcsh").unwrap(), SupportedShell::Tcsh);
+        assert_eq!(SupportedShell::from_str("zsh").unwrap(), SupportedShell::Zsh);
+        assert!(SupportedShell::from_str("ksh").is_err());
+    }
+
+    #[test]
+    fn can_clear_cached_vars_and_aliases() {
+        let vars = CachedEnvVars::from_names("DD_SHOW:DD_SEQUENCE:DD_SHOT");
+        let aliases = CachedAliases::from_names("DD_SHOW:DD_SEQUENCE:DD_SHOT");
+
+        let zsh = SupportedShell::Zsh.get();
+        assert_eq!(vars.clear(&zsh), "unset DD_SHOW;unset DD_SEQUENCE;unset DD_SHOT;");
+        assert_eq!(aliases.clear(&zsh), "unalias DD_SHOW 2>/dev/null;unalias DD_SEQUENCE 2>/dev/null;unalias DD_SHOT 2>/dev/null;");
+
+        let fish = SupportedShell::Fish.get();
+        assert_eq!(vars.clear(&fish), "set -e DD_SHOW;set -e DD_SEQUENCE;set -e DD_SHOT;");
+        assert_eq!(aliases.clear(&fish), "functions -e DD_SHOW;functions -e DD_SEQUENCE;functions -e DD_SHOT;");
+    }
+
+    #[test]
+    fn can_validate_names() {
+        assert!(is_var_name("DD_SHOW"));
+        assert!(is_var_name("_x1"));
+        assert!(!is_var_name("1X"));
+        assert!(!is_var_name("X;rm"));
+        assert!(!is_var_name(""));
+        assert!(is_alias_name("cs.work-1"));
+        assert!(!is_alias_name("-f"));
+        assert!(!is_alias_name("cs work"));
+    }
+
+    #[test]
+    fn can_posix_quote() {
+        assert_eq!(posix_quote("/dd/shows/DEV01"), "/dd/shows/DEV01");
+        assert_eq!(posix_quote(""), "''");
+        assert_eq!(posix_quote("a b"), "'a b'");
+        assert_eq!(posix_quote("it's"), r"'it'\''s'");
+        assert_eq!(posix_quote("$(rm -rf ~);`x`"), "'$(rm -rf ~);`x`'");
+    }
+}
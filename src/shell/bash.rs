@@ -1,4 +1,4 @@
-use crate::ShellEnvManager;
+use crate::{shell, ShellEnvManager};
 use std::path::Path;
 
 /// Struct which implements the `ShellEnvManager` trait for Bash.
@@ -19,27 +19,71 @@ impl Shell {
 
 impl ShellEnvManager for Shell {
 
+    fn quote(&self, value: &str) -> String {
+        shell::posix_quote(value)
+    }
+
     fn set_env_var(&self, varname: &str, value: &str) -> String {
-        format!("export {}={};", varname, value)
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("export {}={};", varname, self.quote(value))
     }
 
     fn unset_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
         format!("unset {};", varname)
     }
 
     fn clear_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
         let  ret = format!("unset {};", varname); // could also be export {}='';
         ret
     }
 
     fn set_alias(&self, name: &str, value: &Path) -> String {
-        let  ret = format!("alias {}='cd {}';", name, value.display()); 
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        let cmd = format!("cd {}", self.quote(&value.to_string_lossy()));
+        let  ret = format!("alias {}={};", name, self.quote(&cmd)); 
         ret
     }
 
     fn unset_alias(&self, name: &str) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
         let  ret = format!("unalias {};", name); 
         ret
     }
 
-}
\ No newline at end of file
+    fn completion_script(&self, commands: &[&str]) -> String {
+        format!(r#"# jsp completion for bash. Add the following to your .bashrc:
+#   eval "$(jsp completion bash)"
+_jsp_complete() {{
+    local line="${{COMP_LINE:0:COMP_POINT}}"
+    local cur="${{line##*[[:space:]]}}"
+    local IFS=$'\n'
+    COMPREPLY=( $(jsp -l error complete --line "$line" 2>/dev/null) )
+    # bash treats ':' as a word break, so strip the key from key:value candidates
+    if [[ "$cur" == *:* && "$COMP_WORDBREAKS" == *:* ]]; then
+        local prefix="${{cur%:*}}:"
+        COMPREPLY=( "${{COMPREPLY[@]#"$prefix"}}" )
+    else
+        # paths, levelspecs, and keys are completed a level at a time
+        compopt -o nospace 2>/dev/null
+    fi
+}}
+complete -F _jsp_complete {}
+"#, commands.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_script_registers_commands() {
+        let bash = Shell::new();
+        let script = bash.completion_script(&["jspgo", "jspmk"]);
+        assert!(script.ends_with("complete -F _jsp_complete jspgo jspmk\n"));
+        assert!(script.contains("_jsp_complete() {\n"));
+        assert!(script.contains(r#"jsp -l error complete --line "$line""#));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{shell, ShellEnvManager};
+use std::path::Path;
+
+/// Struct which implements the `ShellEnvManager` trait for Fish.
+pub struct Shell {}
+
+impl std::default::Default for Shell {
+    fn default() -> Shell {
+        Shell {}
+    }
+}
+
+impl Shell {
+    /// New up an instance of Fish
+    pub fn new() -> Self {
+        Shell::default()
+    }
+}
+
+impl ShellEnvManager for Shell {
+
+    // within single quotes, fish only treats `\'` and `\\` specially
+    fn quote(&self, value: &str) -> String {
+        if shell::is_bare_word(value) {
+            return value.to_string();
+        }
+        format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+    }
+
+    fn set_env_var(&self, varname: &str, value: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("set -gx {} {};", varname, self.quote(value))
+    }
+
+    fn unset_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("set -e {};", varname)
+    }
+
+    fn clear_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("set -e {};", varname)
+    }
+
+    // fish aliases are functions under the hood
+    fn set_alias(&self, name: &str, value: &Path) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        let cmd = format!("cd {}", self.quote(&value.to_string_lossy()));
+        format!("alias {} {};", name, self.quote(&cmd))
+    }
+
+    fn unset_alias(&self, name: &str) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        format!("functions -e {};", name)
+    }
+
+    fn completion_script(&self, commands: &[&str]) -> String {
+        let mut script = String::from("# jsp completion for fish. Add the following to your config.fish:\n");
+        script.push_str("#   jsp completion fish | source\n");
+        for command in commands {
+            script.push_str(&format!("complete -c {} -f -a '(jsp -l error complete --line (commandline -cp) 2>/dev/null)'\n", command));
+        }
+        script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_set_and_unset_env_var() {
+        let fish = Shell::new();
+        assert_eq!(fish.set_env_var("DD_SHOW", "DEV01"), "set -gx DD_SHOW DEV01;");
+        assert_eq!(fish.unset_env_var("DD_SHOW"), "set -e DD_SHOW;");
+        assert_eq!(fish.clear_env_var("DD_SHOW"), "set -e DD_SHOW;");
+    }
+
+    #[test]
+    fn can_set_and_unset_alias() {
+        let fish = Shell::new();
+        assert_eq!(fish.set_alias("cdshot", Path::new("/dd/shows/DEV01/RD/9999")), "alias cdshot 'cd /dd/shows/DEV01/RD/9999';");
+        assert_eq!(fish.unset_alias("cdshot"), "functions -e cdshot;");
+    }
+
+    #[test]
+    fn can_quote_values() {
+        let fish = Shell::new();
+        assert_eq!(fish.quote("/dd/shows/DEV01"), "/dd/shows/DEV01");
+        assert_eq!(fish.quote("it's (rm) $x"), r"'it\'s (rm) $x'");
+        assert_eq!(fish.quote(r"back\slash"), r"'back\\slash'");
+    }
+
+    #[test]
+    fn completion_script_registers_commands() {
+        let fish = Shell::new();
+        let script = fish.completion_script(&["jspgo", "jspmk"]);
+        assert!(script.contains("complete -c jspgo -f -a"));
+        assert!(script.contains("complete -c jspmk -f -a"));
+    }
+}
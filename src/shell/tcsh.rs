@@ -1,4 +1,4 @@
-use crate::ShellEnvManager;
+use crate::{shell, ShellEnvManager};
 use std::path::Path;
 
 /// Struct which implements the `ShellEnvManager` trait for Bash.
@@ -20,26 +20,91 @@ impl Shell {
 
 impl ShellEnvManager for Shell {
 
+    // csh performs history substitution on `!` even within single quotes, so it
+    // is escaped outside of them, as are single quotes and newlines.
+    fn quote(&self, value: &str) -> String {
+        if shell::is_bare_word(value) {
+            return value.to_string();
+        }
+        let mut quoted = String::from("'");
+        for c in value.chars() {
+            match c {
+                '\'' => quoted.push_str(r"'\''"),
+                '!' => quoted.push_str(r"'\!'"),
+                '\n' => quoted.push_str("'\\\n'"),
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('\'');
+        quoted
+    }
+
     fn set_env_var(&self, varname: &str, value: &str) -> String {
-        format!("setenv {} {};", varname, value)
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("setenv {} {};", varname, self.quote(value))
     }
 
     fn unset_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
         format!("setenv {} \"\";", varname)
     }
 
     fn clear_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
         let  ret = format!("unsetenv {};", varname); // could also be export {}='';
         ret
     }
 
     fn set_alias(&self, name: &str, value: &Path) -> String {
-        let  ret = format!("alias {} 'cd {}';", name, value.display()); 
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        let cmd = format!("cd {}", self.quote(&value.to_string_lossy()));
+        let  ret = format!("alias {} {};", name, self.quote(&cmd)); 
         ret
     }
 
     fn unset_alias(&self, name: &str) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
         let  ret = format!("unalias {};", name); 
         ret
     }
-}
\ No newline at end of file
+
+    fn completion_script(&self, commands: &[&str]) -> String {
+        let mut script = String::from("# jsp completion for tcsh. Add the following to your .tcshrc:\n");
+        script.push_str("#   jsp completion tcsh > ~/.jsp_completion.tcsh && source ~/.jsp_completion.tcsh\n");
+        for command in commands {
+            script.push_str(&format!("complete {} 'p,*,`jsp -l error complete --line \"$COMMAND_LINE\"`,'\n", command));
+        }
+        script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_quote_values() {
+        let tcsh = Shell::new();
+        assert_eq!(tcsh.quote("/dd/shows/DEV01"), "/dd/shows/DEV01");
+        assert_eq!(tcsh.quote("it's `rm` $x"), r"'it'\''s `rm` $x'");
+        assert_eq!(tcsh.quote("a!b"), r"'a'\!'b'");
+    }
+
+    #[test]
+    fn can_set_env_var_and_alias() {
+        let tcsh = Shell::new();
+        assert_eq!(tcsh.set_env_var("DD_SHOW", "DEV 01"), "setenv DD_SHOW 'DEV 01';");
+        assert_eq!(tcsh.set_alias("cdshot", Path::new("/dd/shows/DEV01")), "alias cdshot 'cd /dd/shows/DEV01';");
+        assert_eq!(tcsh.set_env_var("DD;SHOW", "DEV01"), "");
+    }
+
+    #[test]
+    fn completion_script_registers_commands() {
+        let script = Shell::new().completion_script(&["jspgo", "jspmk"]);
+        let completions = script.lines().filter(|l| !l.starts_with('#')).collect::<Vec<&str>>();
+        assert_eq!(completions, vec![
+            r#"complete jspgo 'p,*,`jsp -l error complete --line "$COMMAND_LINE"`,'"#,
+            r#"complete jspmk 'p,*,`jsp -l error complete --line "$COMMAND_LINE"`,'"#,
+        ]);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{shell, ShellEnvManager};
+use std::path::Path;
+
+/// Struct which implements the `ShellEnvManager` trait for Zsh.
+pub struct Shell {}
+
+impl std::default::Default for Shell {
+    fn default() -> Shell {
+        Shell {}
+    }
+}
+
+impl Shell {
+    /// New up an instance of Zsh
+    pub fn new() -> Self {
+        Shell::default()
+    }
+}
+
+impl ShellEnvManager for Shell {
+
+    fn quote(&self, value: &str) -> String {
+        shell::posix_quote(value)
+    }
+
+    fn set_env_var(&self, varname: &str, value: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("export {}={};", varname, self.quote(value))
+    }
+
+    fn unset_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("unset {};", varname)
+    }
+
+    fn clear_env_var(&self, varname: &str) -> String {
+        if !shell::is_var_name(varname) { return shell::reject_name("variable", varname); }
+        format!("unset {};", varname)
+    }
+
+    fn set_alias(&self, name: &str, value: &Path) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        let cmd = format!("cd {}", self.quote(&value.to_string_lossy()));
+        format!("alias {}={};", name, self.quote(&cmd))
+    }
+
+    fn unset_alias(&self, name: &str) -> String {
+        if !shell::is_alias_name(name) { return shell::reject_name("alias", name); }
+        // unlike bash, zsh's unalias complains when the alias does not exist
+        format!("unalias {} 2>/dev/null;", name)
+    }
+
+    fn completion_script(&self, commands: &[&str]) -> String {
+        format!(r#"# jsp completion for zsh. Add the following to your .zshrc, after compinit:
+#   eval "$(jsp completion zsh)"
+_jsp_complete() {{
+    local -a candidates
+    candidates=( ${{(f)"$(jsp -l error complete --line "${{BUFFER[1,CURSOR]}}" 2>/dev/null)"}} )
+    # paths, levelspecs, and keys are completed a level at a time
+    compadd -U -S '' -- $candidates
+}}
+compdef _jsp_complete {}
+"#, commands.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_set_and_unset_env_var() {
+        let zsh = Shell::new();
+        assert_eq!(zsh.set_env_var("DD_SHOW", "DEV01"), "export DD_SHOW=DEV01;");
+        assert_eq!(zsh.unset_env_var("DD_SHOW"), "unset DD_SHOW;");
+        assert_eq!(zsh.clear_env_var("DD_SHOW"), "unset DD_SHOW;");
+    }
+
+    #[test]
+    fn can_set_and_unset_alias() {
+        let zsh = Shell::new();
+        assert_eq!(zsh.set_alias("cdshot", Path::new("/dd/shows/DEV01/RD/9999")), "alias cdshot='cd /dd/shows/DEV01/RD/9999';");
+        assert_eq!(zsh.unset_alias("cdshot"), "unalias cdshot 2>/dev/null;");
+    }
+
+    #[test]
+    fn completion_script_registers_commands() {
+        let zsh = Shell::new();
+        let script = zsh.completion_script(&["jspgo", "jspmk"]);
+        assert!(script.contains("compdef _jsp_complete jspgo jspmk\n"));
+        assert!(script.contains("jsp -l error complete --line"));
+    }
+}
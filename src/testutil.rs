@@ -0,0 +1,60 @@
+//! Fixtures shared by the unit tests
+use crate::{jspnode, EntryType, JGraph, NIndex, Node, NodeType};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory beneath the system temp directory, unique to the test and the
+/// process, which is removed along with its contents when dropped. Creating
+/// it panics on failure, so that tests fail rather than silently pass.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty temp directory for the test named `name`
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("jsp_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap_or_else(|e| panic!("unable to create {:?}: {}", path, e));
+        Self { path }
+    }
+
+    /// The path to the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path to `rel` within the directory
+    pub fn join<P: AsRef<Path>>(&self, rel: P) -> PathBuf {
+        self.path.join(rel)
+    }
+
+    /// Create the directories `rels` within the directory
+    pub fn mkdirs(&self, rels: &[&str]) {
+        for rel in rels {
+            fs::create_dir_all(self.join(rel)).unwrap();
+        }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Build a graph whose simple nodes lead from the root to `dir`, returning the
+/// graph along with the index of the node for `dir`, beneath which tests add
+/// their own nodes.
+pub fn graph_to(dir: &Path) -> (JGraph, NIndex) {
+    let mut graph = JGraph::new();
+    let mut parent = graph.add_node(Node::new_root());
+    for component in dir.iter().skip(1) {
+        let idx = graph.add_node(jspnode!(component.to_str().unwrap()));
+        graph.add_edge(parent, idx, ());
+        parent = idx;
+    }
+    (graph, parent)
+}
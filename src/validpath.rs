@@ -1,5 +1,5 @@
 use std::{fmt, path::{Path, PathBuf}};
-use crate::{diskutils, NIndex, NodePath, JGraph, JSPError, validate_path, find_path, find_path_from_terms, SearchTerm, Search};
+use crate::{diskutils, NIndex, NodePath, JGraph, JSPError, validate_path, validate_path_from, find_path, find_path_from_terms, SearchTerm, Search};
 use std::fmt::Debug;
 
 /// A ValidPath provides a pairing of a PathBuf and a NodePath, representing a path that 
@@ -156,6 +156,43 @@ impl<'a> ValidPath<'a> {
         &self.nodepath
     }
 
+    /// Join a relative path onto self, validating it against the template 
+    /// starting from the leaf of self.
+    ///
+    /// # Parameters
+    ///
+    /// * `relpath` - A path relative to self
+    ///
+    /// # Returns
+    /// Ok wrapping a new ValidPath if successful. Otherwise, an Err wrapping a JSPError
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsp::{graph::testdata::build_graph, ValidPath};
+    /// use std::path::PathBuf;
+    ///
+    /// let graph = build_graph();
+    /// let shot = ValidPath::new("/dd/shows/DEV01/RD/9999", &graph, false).unwrap();
+    /// let work = shot.join("user/work.jgerber").unwrap();
+    /// assert_eq!(work.pathbuf(), PathBuf::from("/dd/shows/DEV01/RD/9999/user/work.jgerber"));
+    /// ```
+    pub fn join<I: AsRef<Path> + Debug>(&self, relpath: I) -> Result<ValidPath<'a>, JSPError> {
+        let start = self.nodepath.nindex().ok_or_else(|| JSPError::GeneralError("ValidPath::join(...) empty nodepath".to_string()))?;
+        let relnodes = validate_path_from(relpath.as_ref(), start, self.nodepath.graph())?;
+        Ok(self.join_unchecked(relpath, &relnodes))
+    }
+
+    /// Join a relative path, and the NodePath it was validated against, onto self,
+    /// without validating. The caller is responsible for ensuring that the relative
+    /// path was validated from the leaf of self (eg via `validate_path_from`).
+    pub fn join_unchecked<I: AsRef<Path>>(&self, relpath: I, relnodes: &NodePath<'a>) -> ValidPath<'a> {
+        ValidPath {
+            pathbuf: self.pathbuf.join(relpath),
+            nodepath: self.nodepath.join(relnodes),
+        }
+    }
+
     /// Pop off a ValidPath<'a> from the validpath
     pub fn pop(&mut self) -> Result<ValidPath<'a>, JSPError> {
         // pop off the last NIndex
@@ -222,4 +259,14 @@ mod tests {
         
     }
 
+    #[test]
+    fn can_join_relative_path() {
+        let graph = build_graph();
+        let shot = ValidPath::new("/dd/shows/DEV01/RD/9999", &graph, false).unwrap();
+        let joined = shot.join("SHARED/MODEL").unwrap();
+        assert_eq!(joined.pathbuf(), PathBuf::from("/dd/shows/DEV01/RD/9999/SHARED/MODEL"));
+        assert_eq!(joined, ValidPath::new("/dd/shows/DEV01/RD/9999/SHARED/MODEL", &graph, false).unwrap());
+        assert!(shot.join("FOO").is_err());
+    }
+
 }
\ No newline at end of file